@@ -0,0 +1,206 @@
+use std::{
+    fmt::{Display, Formatter},
+    process::exit,
+};
+
+use anyhow::{anyhow, Result};
+#[cfg(feature = "bigint")]
+use num_bigint::BigUint;
+
+/// A puzzle answer that cannot silently wrap. Values are held as a `u128`; when that overflows
+/// the result is an error, or with the `bigint` feature the value moves to an arbitrary-precision
+/// integer instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Small(u128),
+    #[cfg(feature = "bigint")]
+    Big(BigUint),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Operation {
+    Add,
+    Multiply,
+}
+
+impl Operation {
+    #[cfg(not(feature = "bigint"))]
+    fn symbol(&self) -> char {
+        match self {
+            Operation::Add => '+',
+            Operation::Multiply => '*',
+        }
+    }
+}
+
+impl Default for Answer {
+    fn default() -> Self {
+        Answer::Small(0)
+    }
+}
+
+impl From<u128> for Answer {
+    fn from(value: u128) -> Self {
+        Answer::Small(value)
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(value: usize) -> Self {
+        Answer::Small(value as u128)
+    }
+}
+
+impl Answer {
+    pub fn checked_add(&self, other: &Answer) -> Result<Answer> {
+        self.apply(other, Operation::Add)
+    }
+
+    pub fn checked_mul(&self, other: &Answer) -> Result<Answer> {
+        self.apply(other, Operation::Multiply)
+    }
+
+    /// Adds up every value, stopping at the first overflow.
+    pub fn try_sum<I>(values: I) -> Result<Answer>
+    where
+        I: IntoIterator<Item = Answer>,
+    {
+        values
+            .into_iter()
+            .try_fold(Answer::default(), |total, value| total.checked_add(&value))
+    }
+
+    fn apply(&self, other: &Answer, operation: Operation) -> Result<Answer> {
+        match (self, other) {
+            (Answer::Small(left), Answer::Small(right)) => {
+                let result = match operation {
+                    Operation::Add => left.checked_add(*right),
+                    Operation::Multiply => left.checked_mul(*right),
+                };
+                match result {
+                    Some(value) => Ok(Answer::Small(value)),
+                    None => self.overflow(other, operation),
+                }
+            }
+            #[cfg(feature = "bigint")]
+            _ => self.overflow(other, operation),
+        }
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    fn overflow(&self, other: &Answer, operation: Operation) -> Result<Answer> {
+        Err(anyhow!(
+            "{self} {} {other} overflows a 128-bit answer",
+            operation.symbol()
+        ))
+    }
+
+    #[cfg(feature = "bigint")]
+    fn overflow(&self, other: &Answer, operation: Operation) -> Result<Answer> {
+        let (left, right) = (self.to_big(), other.to_big());
+        let value = match operation {
+            Operation::Add => left + right,
+            Operation::Multiply => left * right,
+        };
+
+        Ok(match u128::try_from(&value) {
+            Ok(small) => Answer::Small(small),
+            Err(_) => Answer::Big(value),
+        })
+    }
+
+    #[cfg(feature = "bigint")]
+    fn to_big(&self) -> BigUint {
+        match self {
+            Answer::Small(value) => BigUint::from(*value),
+            Answer::Big(value) => value.clone(),
+        }
+    }
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Small(value) => write!(f, "{value}"),
+            #[cfg(feature = "bigint")]
+            Answer::Big(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/// Prints a puzzle's answer, or reports why there is none and exits with a failure status.
+pub fn report(result: Result<Answer>) {
+    match result {
+        Ok(answer) => println!("{answer}"),
+        Err(error) => {
+            eprintln!("Error: {error:#}");
+            exit(1);
+        }
+    }
+}
+
+/// Converts a signed intermediate result into an answer, failing if it went negative.
+pub fn from_signed(value: i128) -> Result<Answer> {
+    u128::try_from(value)
+        .map(Answer::Small)
+        .map_err(|_| anyhow!("Answer {value} is negative"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checked_add() {
+        let result = Answer::from(2_usize)
+            .checked_add(&Answer::from(3_usize))
+            .unwrap();
+
+        assert_eq!(result, Answer::from(5_usize));
+        assert_eq!(result.to_string(), "5");
+    }
+
+    #[test]
+    fn test_try_sum() {
+        let values = [1_usize, 2, 3].map(Answer::from);
+
+        assert_eq!(Answer::try_sum(values).unwrap(), Answer::from(6_usize));
+    }
+
+    #[test]
+    fn test_from_signed() {
+        assert_eq!(from_signed(7).unwrap(), Answer::from(7_usize));
+        assert!(from_signed(-1).is_err());
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    #[test]
+    fn test_overflow_is_an_error() {
+        let result = Answer::from(u128::MAX).checked_add(&Answer::from(1_usize));
+
+        assert!(result.is_err());
+        assert!(Answer::from(u128::MAX)
+            .checked_mul(&Answer::from(2_usize))
+            .is_err());
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_overflow_promotes() {
+        let result = Answer::from(u128::MAX)
+            .checked_add(&Answer::from(1_usize))
+            .unwrap();
+
+        assert_eq!(
+            result.to_string(),
+            "340282366920938463463374607431768211456"
+        );
+        assert_eq!(
+            Answer::from(u128::MAX)
+                .checked_mul(&Answer::from(2_usize))
+                .unwrap()
+                .to_string(),
+            "680564733841876926926749214863536422910"
+        );
+    }
+}
@@ -1,9 +1,11 @@
 use std::{
+    cmp::Reverse,
+    collections::HashMap,
     fs::File,
     io::{BufReader, Lines},
 };
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 
 use crate::{
     utils::{build_data_file_path, read_lines},
@@ -24,8 +26,24 @@ impl Values {
     }
 }
 
-pub fn puzzle1() {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Pair {
+    left: usize,
+    right: usize,
+    distance: usize,
+}
+
+pub fn puzzle1(top: Option<usize>) {
     let file_path = build_data_file_path(&Day::Day1, "data.txt").unwrap();
+
+    if let Some(count) = top {
+        let lines = read_lines(&file_path).unwrap();
+        let values = gather_values(lines).unwrap();
+        for pair in largest_distances(paired_distances(values), count) {
+            println!("{} {} {}", pair.left, pair.right, pair.distance);
+        }
+    }
+
     let lines = read_lines(file_path).unwrap();
     let result = calculate_part_one(lines).unwrap();
     println!("{result}");
@@ -39,52 +57,94 @@ pub fn puzzle2() {
 }
 
 fn calculate_part_one(lines: Lines<BufReader<File>>) -> Result<usize> {
-    let mut values = gather_values(lines).unwrap();
-    values.left.sort();
-    values.right.sort();
-
-    let total = values
-        .left
+    let values = gather_values(lines)?;
+    let total = paired_distances(values)
         .iter()
-        .zip(&values.right)
-        .map(|(left, right)| left.abs_diff(*right))
+        .map(|pair| pair.distance)
         .sum();
 
     Ok(total)
 }
 
+/// Counts how often each value appears on either side, so the score never has to scan the right
+/// list more than once and only the distinct values are kept in memory.
 fn calculate_part_two(lines: Lines<BufReader<File>>) -> Result<usize> {
-    let values = gather_values(lines).unwrap();
+    let mut left_counts: HashMap<usize, usize> = HashMap::new();
+    let mut right_counts: HashMap<usize, usize> = HashMap::new();
 
-    let total = values
-        .left
+    for pair in parse_pairs(lines) {
+        let (left, right) = pair?;
+        *left_counts.entry(left).or_default() += 1;
+        *right_counts.entry(right).or_default() += 1;
+    }
+
+    let total = left_counts
         .iter()
-        .map(|value| {
-            let count = values.right.iter().filter(|&x| x == value).count();
-            value * count
-        })
+        .map(|(value, count)| value * count * right_counts.get(value).unwrap_or(&0))
         .sum();
 
     Ok(total)
 }
 
+fn parse_pairs(lines: Lines<BufReader<File>>) -> impl Iterator<Item = Result<(usize, usize)>> {
+    lines.enumerate().map(|(index, line)| {
+        let line = line.with_context(|| format!("Unable to read line {}", index + 1))?;
+        parse_line(&line).with_context(|| format!("Invalid input on line {}", index + 1))
+    })
+}
+
+fn parse_line(line: &str) -> Result<(usize, usize)> {
+    let parts = line.split_whitespace().collect::<Vec<&str>>();
+    if parts.len() != 2 {
+        bail!("Incorrect number of values in line: {line}");
+    }
+    let left = parts[0]
+        .parse::<usize>()
+        .with_context(|| format!("Invalid left value: {}", parts[0]))?;
+    let right = parts[1]
+        .parse::<usize>()
+        .with_context(|| format!("Invalid right value: {}", parts[1]))?;
+
+    Ok((left, right))
+}
+
 fn gather_values(lines: Lines<BufReader<File>>) -> Result<Values> {
     let mut values = Values::new();
 
-    for line in lines.map_while(Result::ok) {
-        let parts = line.split_whitespace().collect::<Vec<&str>>();
-        if parts.len() != 2 {
-            bail!("Incorrect number of values in line: {line}");
-        }
-        let left_val = parts[0].parse::<usize>().unwrap();
-        values.left.push(left_val);
-        let right_val = parts[1].parse::<usize>().unwrap();
-        values.right.push(right_val);
+    for pair in parse_pairs(lines) {
+        let (left, right) = pair?;
+        values.left.push(left);
+        values.right.push(right);
     }
 
     Ok(values)
 }
 
+/// Pairs the smallest left value with the smallest right value, the second smallest with the
+/// second smallest, and so on.
+fn paired_distances(mut values: Values) -> Vec<Pair> {
+    values.left.sort_unstable();
+    values.right.sort_unstable();
+
+    values
+        .left
+        .iter()
+        .zip(&values.right)
+        .map(|(&left, &right)| Pair {
+            left,
+            right,
+            distance: left.abs_diff(right),
+        })
+        .collect()
+}
+
+fn largest_distances(mut pairs: Vec<Pair>, count: usize) -> Vec<Pair> {
+    pairs.sort_by_key(|pair| Reverse(pair.distance));
+    pairs.truncate(count);
+
+    pairs
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -131,4 +191,54 @@ mod tests {
 
         assert_eq!(result, 31);
     }
+
+    #[test]
+    fn test_paired_distances() {
+        let base = tempdir().unwrap().path().to_path_buf();
+        create_dir_all(&base).unwrap();
+        let file_path = base.join("data.txt");
+        let mut file = File::create(&file_path).unwrap();
+        let data = r#"3   4
+4   3
+2   5
+1   3
+3   9
+3   3"#;
+        file.write_all(data.as_bytes()).unwrap();
+        let lines = read_lines(file_path).unwrap();
+        let values = gather_values(lines).unwrap();
+        let pairs = paired_distances(values);
+
+        assert_eq!(
+            pairs[0],
+            Pair {
+                left: 1,
+                right: 3,
+                distance: 2
+            }
+        );
+        assert_eq!(
+            largest_distances(pairs, 1),
+            vec![Pair {
+                left: 4,
+                right: 9,
+                distance: 5
+            }]
+        );
+    }
+
+    #[test]
+    fn test_malformed_line() {
+        let base = tempdir().unwrap().path().to_path_buf();
+        create_dir_all(&base).unwrap();
+        let file_path = base.join("data.txt");
+        let mut file = File::create(&file_path).unwrap();
+        let data = r#"3   4
+4   x"#;
+        file.write_all(data.as_bytes()).unwrap();
+        let lines = read_lines(file_path).unwrap();
+        let result = calculate_part_two(lines).unwrap_err();
+
+        assert_eq!(result.to_string(), "Invalid input on line 2");
+    }
 }
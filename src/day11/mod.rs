@@ -1,115 +1,123 @@
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::HashMap,
     fs::File,
     io::{BufReader, Lines},
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 use crate::{
-    utils::{build_data_file_path, read_lines, split_whitespace_to_usize},
+    answer::{self, Answer},
+    utils::{build_data_file_path, digits::split_half, read_lines, split_whitespace_to_usize},
     Day,
 };
 
-pub fn puzzle1() {
-    let file_path = build_data_file_path(&Day::Day11, "data.txt").unwrap();
-    let lines = read_lines(file_path).unwrap();
-    let result = calculate_part_one(lines).unwrap();
+const PART_ONE_BLINKS: usize = 25;
+const PART_TWO_BLINKS: usize = 75;
 
-    println!("{result}");
+/// What happens to a stone on each blink. The first rule that applies wins: zero stones are
+/// replaced, stones with an even number of digits split in half, everything else is multiplied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules {
+    zero_becomes: usize,
+    split_even_digits: bool,
+    multiplier: usize,
 }
 
-pub fn puzzle2() {
-    let file_path = build_data_file_path(&Day::Day11, "data.txt").unwrap();
-    let lines = read_lines(file_path).unwrap();
-    let result = calculate_part_two(lines).unwrap();
-
-    println!("{result}");
+impl Default for Rules {
+    fn default() -> Self {
+        Self::new(1, true, 2024)
+    }
 }
 
-fn calculate_part_one(lines: Lines<BufReader<File>>) -> Result<usize> {
-    let mut numbers = VecDeque::new();
+impl Rules {
+    pub fn new(zero_becomes: usize, split_even_digits: bool, multiplier: usize) -> Self {
+        Self {
+            zero_becomes,
+            split_even_digits,
+            multiplier,
+        }
+    }
+
+    fn apply(&self, value: usize) -> Result<(usize, Option<usize>)> {
+        if value == 0 {
+            return Ok((self.zero_becomes, None));
+        }
 
-    for line in lines.map_while(Result::ok) {
-        let nums = split_whitespace_to_usize(&line);
-        for num in nums {
-            numbers.push_back(num);
+        if self.split_even_digits {
+            if let Some((left, right)) = split_half(value) {
+                return Ok((left, Some(right)));
+            }
         }
+
+        value
+            .checked_mul(self.multiplier)
+            .map(|next| (next, None))
+            .ok_or_else(|| {
+                anyhow!(
+                    "Stone {value} overflowed when multiplied by {}",
+                    self.multiplier
+                )
+            })
     }
+}
 
-    let numbers = do_calculations_part_one(&mut numbers, 25);
+pub fn puzzle1(blinks: Option<usize>, rules: &Rules) {
+    let file_path = build_data_file_path(&Day::Day11, "data.txt").unwrap();
+    let lines = read_lines(file_path).unwrap();
+    answer::report(count_stones(
+        lines,
+        blinks.unwrap_or(PART_ONE_BLINKS),
+        rules,
+    ));
+}
 
-    Ok(numbers.len())
+pub fn puzzle2(blinks: Option<usize>, rules: &Rules) {
+    let file_path = build_data_file_path(&Day::Day11, "data.txt").unwrap();
+    let lines = read_lines(file_path).unwrap();
+    answer::report(count_stones(
+        lines,
+        blinks.unwrap_or(PART_TWO_BLINKS),
+        rules,
+    ));
 }
 
-fn calculate_part_two(lines: Lines<BufReader<File>>) -> Result<usize> {
-    let mut total = 0;
-    let mut tracker = HashMap::new();
-    let rounds = 75;
+fn count_stones(lines: Lines<BufReader<File>>, blinks: usize, rules: &Rules) -> Result<Answer> {
+    let mut stones = HashMap::new();
 
     for line in lines.map_while(Result::ok) {
-        let nums = split_whitespace_to_usize(&line);
-        for num in nums {
-            total += do_calculations_part_two(num, rounds, &mut tracker);
+        for num in split_whitespace_to_usize(&line) {
+            add_stones(&mut stones, num, &Answer::from(1_usize))?;
         }
     }
 
-    Ok(total)
-}
-
-fn do_calculations_part_two(
-    value: usize,
-    remaining: usize,
-    tracker: &mut HashMap<(usize, usize), usize>,
-) -> usize {
-    if tracker.contains_key(&(value, remaining)) {
-        tracker[&(value, remaining)]
-    } else if remaining == 0 {
-        1
-    } else {
-        let count = if value == 0 {
-            do_calculations_part_two(1, remaining - 1, tracker)
-        } else if value.to_string().len() % 2 == 0 {
-            let values = split_num(value);
-            [values.0, values.1]
-                .iter()
-                .map(|v| do_calculations_part_two(*v, remaining - 1, tracker))
-                .sum()
-        } else {
-            do_calculations_part_two(value * 2024, remaining - 1, tracker)
-        };
-
-        tracker.insert((value, remaining), count);
-        count
+    for _ in 0..blinks {
+        stones = blink(&stones, rules)?;
     }
+
+    Answer::try_sum(stones.into_values())
 }
 
-fn do_calculations_part_one(numbers: &mut VecDeque<usize>, rounds: usize) -> &mut VecDeque<usize> {
-    for _ in 0..rounds {
-        let current_len = numbers.len();
-
-        for _ in 0..current_len {
-            let num = numbers.pop_back().unwrap();
-            if num == 0 {
-                numbers.push_front(num + 1);
-            } else if num.to_string().len() % 2 == 0 {
-                let (v1, v2) = split_num(num);
-                numbers.push_front(v2);
-                numbers.push_front(v1);
-            } else {
-                numbers.push_front(num * 2024);
-            }
+/// Stones with the same value always evolve the same way, so only the count per value is kept.
+fn blink(stones: &HashMap<usize, Answer>, rules: &Rules) -> Result<HashMap<usize, Answer>> {
+    let mut next = HashMap::with_capacity(stones.len() * 2);
+
+    for (&value, count) in stones {
+        let (first, second) = rules.apply(value)?;
+        add_stones(&mut next, first, count)?;
+        if let Some(second) = second {
+            add_stones(&mut next, second, count)?;
         }
     }
 
-    numbers
+    Ok(next)
 }
 
-fn split_num(num: usize) -> (usize, usize) {
-    let mut temp = num.to_string();
-    let split = temp.len() / 2;
-    let (v1, v2) = temp.split_at_mut(split);
-    (v1.parse::<usize>().unwrap(), v2.parse::<usize>().unwrap())
+fn add_stones(stones: &mut HashMap<usize, Answer>, value: usize, count: &Answer) -> Result<()> {
+    let total = stones.entry(value).or_default();
+    *total = total.checked_add(count)?;
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -130,9 +138,9 @@ mod tests {
         let data = r#"125 17"#;
         file.write_all(data.as_bytes()).unwrap();
         let lines = read_lines(file_path).unwrap();
-        let result = calculate_part_one(lines).unwrap();
+        let result = count_stones(lines, PART_ONE_BLINKS, &Rules::default()).unwrap();
 
-        assert_eq!(result, 55312);
+        assert_eq!(result, Answer::from(55312_usize));
     }
 
     #[test]
@@ -144,15 +152,58 @@ mod tests {
         let data = r#"125 17"#;
         file.write_all(data.as_bytes()).unwrap();
         let lines = read_lines(file_path).unwrap();
-        let result = calculate_part_two(lines).unwrap();
+        let result = count_stones(lines, PART_TWO_BLINKS, &Rules::default()).unwrap();
+
+        assert_eq!(result, Answer::from(65601038650482_usize));
+    }
+
+    #[test]
+    fn test_blink_counts() {
+        let counts = |pairs: &[(usize, usize)]| {
+            pairs
+                .iter()
+                .map(|&(value, count)| (value, Answer::from(count)))
+                .collect::<HashMap<usize, Answer>>()
+        };
+        let stones = counts(&[(0, 2), (1000, 1), (7, 3)]);
+        let result = blink(&stones, &Rules::default()).unwrap();
+
+        assert_eq!(result, counts(&[(1, 2), (10, 1), (0, 1), (14168, 3)]));
+    }
+
+    #[test]
+    fn test_custom_rules() {
+        let base = tempdir().unwrap().path().to_path_buf();
+        create_dir_all(&base).unwrap();
+        let file_path = base.join("data.txt");
+        let mut file = File::create(&file_path).unwrap();
+        let data = r#"0 12"#;
+        file.write_all(data.as_bytes()).unwrap();
+        let lines = read_lines(file_path).unwrap();
+        let result = count_stones(lines, 3, &Rules::new(5, false, 2)).unwrap();
+
+        assert_eq!(result, Answer::from(2_usize));
+        assert_eq!(Rules::new(5, false, 2).apply(12).unwrap(), (24, None));
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    #[test]
+    fn test_count_overflow_is_an_error() {
+        let base = tempdir().unwrap().path().to_path_buf();
+        create_dir_all(&base).unwrap();
+        let file_path = base.join("data.txt");
+        let mut file = File::create(&file_path).unwrap();
+        file.write_all(b"125 17").unwrap();
+        let lines = read_lines(file_path).unwrap();
+        let result = count_stones(lines, 300, &Rules::default());
 
-        assert_eq!(result, 65601038650482);
+        assert!(result.is_err());
     }
 
     #[test]
-    fn test_split_num() {
-        let result = split_num(1000);
+    fn test_multiplier_overflow() {
+        let result = Rules::new(1, false, usize::MAX).apply(3);
 
-        assert_eq!(result, (10, 0));
+        assert!(result.is_err());
     }
 }
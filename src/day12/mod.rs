@@ -1,167 +1,506 @@
 use std::{
-    collections::{HashMap, HashSet, VecDeque},
-    fs::File,
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt::{Display, Formatter},
+    fs::{read_to_string, File},
     io::{BufReader, Lines},
+    path::Path,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
 
 use crate::{
+    render::{ImageFormat, Renderer},
     utils::{build_data_file_path, build_twod_vec, is_in_bounds, read_lines},
     Day,
 };
 
 const DIRECTIONS: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+const DIAGONALS: [(isize, isize); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+const ALL_DIRECTIONS: [(isize, isize); 8] = [
+    (-1, 0),
+    (1, 0),
+    (0, -1),
+    (0, 1),
+    (-1, -1),
+    (-1, 1),
+    (1, -1),
+    (1, 1),
+];
 
-pub fn puzzle1() {
-    let file_path = build_data_file_path(&Day::Day12, "data.txt").unwrap();
-    let lines = read_lines(file_path).unwrap();
-    let result = calculate_part_one(lines).unwrap();
+/// Which neighbours of a plot count as touching when growing a region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Connectivity {
+    /// Plots touch along an edge only.
+    Four,
+    /// Plots also touch at a corner.
+    Eight,
+}
 
-    println!("{result}");
+impl Connectivity {
+    fn neighbours(&self) -> &'static [(isize, isize)] {
+        match self {
+            Connectivity::Four => &DIRECTIONS,
+            Connectivity::Eight => &ALL_DIRECTIONS,
+        }
+    }
+
+    /// The connectivity of the space around a region, so a diagonal gap in a 4-connected region
+    /// lets the outside in and a diagonal touch in an 8-connected one closes it off.
+    fn dual(&self) -> Connectivity {
+        match self {
+            Connectivity::Four => Connectivity::Eight,
+            Connectivity::Eight => Connectivity::Four,
+        }
+    }
 }
 
-pub fn puzzle2() {
-    let file_path = build_data_file_path(&Day::Day12, "data.txt").unwrap();
-    let lines = read_lines(file_path).unwrap();
-    let result = calculate_part_two(lines).unwrap();
+/// Inclusive row and column limits of a region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Bounds {
+    top: usize,
+    left: usize,
+    bottom: usize,
+    right: usize,
+}
 
-    println!("{result}");
+impl Bounds {
+    fn height(&self) -> usize {
+        self.bottom - self.top + 1
+    }
+
+    fn width(&self) -> usize {
+        self.right - self.left + 1
+    }
 }
 
-fn calculate_part_one(lines: Lines<BufReader<File>>) -> Result<usize> {
-    let mut total = 0;
-    let grid = build_twod_vec(lines).unwrap();
-    let mut queue = VecDeque::new();
-    let mut checked = HashSet::new();
+#[derive(Debug, Clone)]
+struct Region {
+    plant: char,
+    cells: Vec<(usize, usize)>,
+    perimeter: usize,
+    sides: usize,
+    bounds: Bounds,
+    holes: usize,
+}
 
-    for row in 0..grid.len() {
-        for col in 0..grid[0].len() {
-            if checked.contains(&(row, col)) {
+impl Region {
+    fn area(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+impl Display for Region {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let bounds = &self.bounds;
+        write!(
+            f,
+            "{}: area {}, perimeter {}, sides {}, bounds ({}, {})-({}, {}), holes {}",
+            self.plant,
+            self.area(),
+            self.perimeter,
+            self.sides,
+            bounds.top,
+            bounds.left,
+            bounds.bottom,
+            bounds.right,
+            self.holes
+        )
+    }
+}
+
+/// How the fence around a region is charged for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PricingModel {
+    /// Area times the length of the fence.
+    Perimeter,
+    /// Area times the number of straight sides, the bulk discount.
+    Sides,
+    /// One post for every corner of the fence, whatever the area.
+    Corners,
+}
+
+impl PricingModel {
+    fn price(&self, region: &Region) -> usize {
+        match self {
+            PricingModel::Perimeter => region.area() * region.perimeter,
+            PricingModel::Sides => region.area() * region.sides,
+            // Each side of a closed fence ends in exactly one corner.
+            PricingModel::Corners => region.sides,
+        }
+    }
+}
+
+/// Per plant multipliers applied on top of the pricing model. Plants that are not listed cost
+/// the normal price.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PriceTable {
+    multipliers: HashMap<char, usize>,
+}
+
+impl PriceTable {
+    pub fn load(file_path: &Path) -> Result<Self> {
+        let data = read_to_string(file_path)
+            .with_context(|| format!("Unable to read price table {}", file_path.display()))?;
+
+        Self::parse(&data)
+    }
+
+    /// Reads one `<plant> <multiplier>` pair per line. Blank lines and `#` comments are skipped.
+    fn parse(data: &str) -> Result<Self> {
+        let mut multipliers = HashMap::new();
+
+        for (index, line) in data.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
                 continue;
             }
-            queue.push_back((row, col));
-            let mut area = 0;
-            let mut perim = 0;
-            let mut perim_tracker: HashMap<(isize, isize), HashSet<(isize, isize)>> =
-                HashMap::new();
-            while !queue.is_empty() {
-                let (row2, col2) = queue.pop_front().unwrap();
-                if checked.contains(&(row2, col2)) {
+
+            let invalid = || anyhow!("Invalid price table entry on line {}: {line}", index + 1);
+            let (plant, multiplier) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let mut chars = plant.chars();
+            let (Some(plant), None) = (chars.next(), chars.next()) else {
+                return Err(invalid());
+            };
+            let multiplier = multiplier.trim().parse::<usize>().map_err(|_| invalid())?;
+            multipliers.insert(plant, multiplier);
+        }
+
+        Ok(Self { multipliers })
+    }
+
+    fn multiplier(&self, plant: char) -> usize {
+        self.multipliers.get(&plant).copied().unwrap_or(1)
+    }
+}
+
+/// Everything that changes how the garden is split up and priced.
+#[derive(Debug, Clone)]
+pub struct Options {
+    connectivity: Connectivity,
+    pricing: Option<PricingModel>,
+    table: PriceTable,
+    /// Whether the pricing was changed from the puzzle's own, in which case the cost per plant
+    /// type is printed along with the total.
+    show_breakdown: bool,
+}
+
+impl Options {
+    pub fn new(
+        connectivity: Connectivity,
+        pricing: Option<PricingModel>,
+        price_table: Option<&Path>,
+    ) -> Result<Self> {
+        let table = price_table.map(PriceTable::load).transpose()?;
+
+        Ok(Self {
+            connectivity,
+            pricing,
+            show_breakdown: pricing.is_some() || table.is_some(),
+            table: table.unwrap_or_default(),
+        })
+    }
+}
+
+/// Fence costs added up per plant type.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Breakdown {
+    plants: BTreeMap<char, PlantCost>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct PlantCost {
+    regions: usize,
+    area: usize,
+    price: usize,
+}
+
+impl Breakdown {
+    fn new(regions: &[Region], model: PricingModel, table: &PriceTable) -> Self {
+        let mut plants: BTreeMap<char, PlantCost> = BTreeMap::new();
+        for region in regions {
+            let cost = plants.entry(region.plant).or_default();
+            cost.regions += 1;
+            cost.area += region.area();
+            cost.price += model.price(region) * table.multiplier(region.plant);
+        }
+
+        Self { plants }
+    }
+
+    fn total(&self) -> usize {
+        self.plants.values().map(|cost| cost.price).sum()
+    }
+}
+
+impl Display for Breakdown {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (plant, cost) in &self.plants {
+            writeln!(
+                f,
+                "{plant}: {} regions, area {}, price {}",
+                cost.regions, cost.area, cost.price
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits a garden into regions in one labelling pass and measures each of them.
+#[derive(Debug)]
+struct RegionAnalyzer<'a> {
+    grid: &'a [Vec<char>],
+    connectivity: Connectivity,
+    labels: Vec<Vec<usize>>,
+}
+
+impl<'a> RegionAnalyzer<'a> {
+    fn new(grid: &'a [Vec<char>], connectivity: Connectivity) -> Self {
+        Self {
+            grid,
+            connectivity,
+            labels: label_regions(grid, connectivity),
+        }
+    }
+
+    fn regions(&self) -> Vec<Region> {
+        let mut cells_by_label: Vec<Vec<(usize, usize)>> = Vec::new();
+        for (row, labels) in self.labels.iter().enumerate() {
+            for (col, &label) in labels.iter().enumerate() {
+                if label == cells_by_label.len() {
+                    cells_by_label.push(Vec::new());
+                }
+                cells_by_label[label].push((row, col));
+            }
+        }
+
+        cells_by_label
+            .into_iter()
+            .enumerate()
+            .map(|(label, cells)| self.measure(label, cells))
+            .collect()
+    }
+
+    fn measure(&self, label: usize, cells: Vec<(usize, usize)>) -> Region {
+        let (first_row, first_col) = cells[0];
+        let mut perimeter = 0;
+        let mut sides = 0;
+        let mut bounds = Bounds {
+            top: first_row,
+            left: first_col,
+            bottom: first_row,
+            right: first_col,
+        };
+
+        for &(row, col) in &cells {
+            let (r, c) = (row as isize, col as isize);
+            perimeter += DIRECTIONS
+                .iter()
+                .filter(|(dr, dc)| !self.is_in_region(label, r + dr, c + dc))
+                .count();
+
+            // Every corner of the outline starts a new side, whether it turns out or in.
+            for (dr, dc) in DIAGONALS {
+                let vertical = self.is_in_region(label, r + dr, c);
+                let horizontal = self.is_in_region(label, r, c + dc);
+                let diagonal = self.is_in_region(label, r + dr, c + dc);
+                if (!vertical && !horizontal) || (vertical && horizontal && !diagonal) {
+                    sides += 1;
+                }
+            }
+
+            bounds.top = bounds.top.min(row);
+            bounds.left = bounds.left.min(col);
+            bounds.bottom = bounds.bottom.max(row);
+            bounds.right = bounds.right.max(col);
+        }
+
+        Region {
+            plant: self.grid[first_row][first_col],
+            holes: self.count_holes(label, &bounds),
+            cells,
+            perimeter,
+            sides,
+            bounds,
+        }
+    }
+
+    /// Flood fills the space around the region inside its bounds padded by one plot. Whatever
+    /// cannot be reached from the padding is enclosed, and each separate pocket is a hole.
+    fn count_holes(&self, label: usize, bounds: &Bounds) -> usize {
+        let (height, width) = (bounds.height() + 2, bounds.width() + 2);
+        let is_open = |row: usize, col: usize| {
+            !self.is_in_region(
+                label,
+                (bounds.top + row) as isize - 1,
+                (bounds.left + col) as isize - 1,
+            )
+        };
+        let mut seen = vec![vec![false; width]; height];
+        let mut holes = 0;
+
+        for row in 0..height {
+            for col in 0..width {
+                if seen[row][col] || !is_open(row, col) {
                     continue;
                 }
-                checked.insert((row2, col2));
-                area += 1;
-                for (dr, dc) in DIRECTIONS {
-                    let next_row = row2 as isize + dr;
-                    let next_col = col2 as isize + dc;
-                    if is_in_bounds(&grid, next_row, next_col)
-                        && grid[next_row as usize][next_col as usize] == grid[row2][col2]
-                    {
-                        queue.push_back((next_row as usize, next_col as usize));
-                    } else {
-                        perim += 1;
-                        perim_tracker
-                            .entry((dr, dc))
-                            .and_modify(|x| {
-                                x.insert((dr, dc));
-                            })
-                            .or_insert_with(|| {
-                                let mut set = HashSet::new();
-                                set.insert((dr, dc));
-                                set
-                            });
+
+                seen[row][col] = true;
+                let mut queue = VecDeque::from([(row, col)]);
+                while let Some((row2, col2)) = queue.pop_front() {
+                    for (dr, dc) in self.connectivity.dual().neighbours() {
+                        let next_row = row2 as isize + dr;
+                        let next_col = col2 as isize + dc;
+                        if is_in_bounds(&seen, next_row, next_col) {
+                            let (next_row, next_col) = (next_row as usize, next_col as usize);
+                            if !seen[next_row][next_col] && is_open(next_row, next_col) {
+                                seen[next_row][next_col] = true;
+                                queue.push_back((next_row, next_col));
+                            }
+                        }
                     }
                 }
+
+                // Scanning starts in the padding, so the first pocket is always the outside.
+                if row != 0 || col != 0 {
+                    holes += 1;
+                }
             }
+        }
+
+        holes
+    }
+
+    fn is_in_region(&self, label: usize, row: isize, col: isize) -> bool {
+        is_in_bounds(&self.labels, row, col) && self.labels[row as usize][col as usize] == label
+    }
+}
+
+pub fn puzzle1(render: Option<&Path>, options: &Options, details: bool) {
+    let file_path = build_data_file_path(&Day::Day12, "data.txt").unwrap();
+    let lines = read_lines(&file_path).unwrap();
+    let result = calculate_part_one(lines, options, details).unwrap();
 
-            total += area * perim;
+    if let Some(dir) = render {
+        let grid = build_twod_vec(read_lines(&file_path).unwrap()).unwrap();
+        render_regions(&grid, options.connectivity, dir, "day12_puzzle1").unwrap();
+    }
+
+    println!("{result}");
+}
+
+pub fn puzzle2(render: Option<&Path>, options: &Options, details: bool) {
+    let file_path = build_data_file_path(&Day::Day12, "data.txt").unwrap();
+    let lines = read_lines(&file_path).unwrap();
+    let result = calculate_part_two(lines, options, details).unwrap();
+
+    if let Some(dir) = render {
+        let grid = build_twod_vec(read_lines(&file_path).unwrap()).unwrap();
+        render_regions(&grid, options.connectivity, dir, "day12_puzzle2").unwrap();
+    }
+
+    println!("{result}");
+}
+
+fn calculate_part_one(
+    lines: Lines<BufReader<File>>,
+    options: &Options,
+    details: bool,
+) -> Result<usize> {
+    let model = options.pricing.unwrap_or(PricingModel::Perimeter);
+
+    calculate_price(lines, options, model, details)
+}
+
+fn calculate_part_two(
+    lines: Lines<BufReader<File>>,
+    options: &Options,
+    details: bool,
+) -> Result<usize> {
+    let model = options.pricing.unwrap_or(PricingModel::Sides);
+
+    calculate_price(lines, options, model, details)
+}
+
+/// Prices every region with the chosen model. With `details` each region is printed before the
+/// total, and the cost per plant type is printed whenever the pricing was customised.
+fn calculate_price(
+    lines: Lines<BufReader<File>>,
+    options: &Options,
+    model: PricingModel,
+    details: bool,
+) -> Result<usize> {
+    let grid = build_twod_vec(lines)?;
+    let regions = RegionAnalyzer::new(&grid, options.connectivity).regions();
+    let breakdown = Breakdown::new(&regions, model, &options.table);
+
+    if details {
+        for region in &regions {
+            println!("{region}");
         }
     }
+    if options.show_breakdown {
+        print!("{breakdown}");
+    }
 
-    Ok(total)
+    Ok(breakdown.total())
 }
 
-fn calculate_part_two(lines: Lines<BufReader<File>>) -> Result<usize> {
-    let mut total = 0;
-    let grid = build_twod_vec(lines).unwrap();
-    let mut queue = VecDeque::new();
-    let mut checked = HashSet::new();
+/// Gives every cell the id of the region it belongs to, numbering regions in scan order.
+fn label_regions(grid: &[Vec<char>], connectivity: Connectivity) -> Vec<Vec<usize>> {
+    let mut labels = vec![vec![usize::MAX; grid[0].len()]; grid.len()];
+    let mut next_label = 0;
 
     for row in 0..grid.len() {
         for col in 0..grid[0].len() {
-            if checked.contains(&(row, col)) {
+            if labels[row][col] != usize::MAX {
                 continue;
             }
-            queue.push_back((row, col));
-            let mut area = 0;
-            let mut perim_tracker: HashMap<(isize, isize), HashSet<(isize, isize)>> =
-                HashMap::new();
-            while !queue.is_empty() {
-                let (row2, col2) = queue.pop_front().unwrap();
-                if checked.contains(&(row2, col2)) {
-                    continue;
-                }
-                checked.insert((row2, col2));
-                area += 1;
-                for (dr, dc) in DIRECTIONS {
+
+            let mut queue = VecDeque::from([(row, col)]);
+            labels[row][col] = next_label;
+            while let Some((row2, col2)) = queue.pop_front() {
+                for (dr, dc) in connectivity.neighbours() {
                     let next_row = row2 as isize + dr;
                     let next_col = col2 as isize + dc;
-                    if is_in_bounds(&grid, next_row, next_col)
-                        && grid[next_row as usize][next_col as usize] == grid[row2][col2]
-                    {
-                        queue.push_back((next_row as usize, next_col as usize));
-                    } else {
-                        perim_tracker
-                            .entry((dr, dc))
-                            .and_modify(|x| {
-                                x.insert((next_row, next_col));
-                            })
-                            .or_insert_with(|| {
-                                let mut set = HashSet::new();
-                                set.insert((next_row, next_col));
-                                set
-                            });
-                    }
-                }
-            }
-
-            let mut sides = 0;
-            for (_, v) in perim_tracker.iter() {
-                let mut checked_perim: HashSet<(isize, isize)> = HashSet::new();
-                for &(prev_row, prev_col) in v {
-                    if !checked_perim.contains(&(prev_row, prev_col)) {
-                        sides += 1;
-                        let mut perim_queue = VecDeque::new();
-                        perim_queue.push_back((prev_row, prev_col));
-                        while !perim_queue.is_empty() {
-                            let (new_row, new_col) = perim_queue.pop_front().unwrap();
-                            if checked_perim.contains(&(new_row, new_col)) {
-                                continue;
-                            }
-                            checked_perim.insert((new_row, new_col));
-                            for (dr, dc) in DIRECTIONS {
-                                let nr = new_row + dr;
-                                let nc = new_col + dc;
-                                if v.contains(&(nr, nc)) {
-                                    perim_queue.push_back((nr, nc));
-                                }
-                            }
+                    if is_in_bounds(grid, next_row, next_col) {
+                        let (next_row, next_col) = (next_row as usize, next_col as usize);
+                        if labels[next_row][next_col] == usize::MAX
+                            && grid[next_row][next_col] == grid[row2][col2]
+                        {
+                            labels[next_row][next_col] = next_label;
+                            queue.push_back((next_row, next_col));
                         }
                     }
                 }
             }
-            println!("{:?}", perim_tracker);
-            total += area * sides;
+            next_label += 1;
         }
     }
 
-    Ok(total)
+    labels
+}
+
+fn render_regions(
+    grid: &[Vec<char>],
+    connectivity: Connectivity,
+    dir: &Path,
+    name: &str,
+) -> Result<()> {
+    let regions = label_regions(grid, connectivity);
+    let renderer = Renderer::new(grid).with_regions(&regions);
+    renderer.write_ascii(dir, name)?;
+    renderer.write_image(dir, name, ImageFormat::Ppm)?;
+    renderer.write_svg(dir, name)?;
+
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::grid_from_str;
     use std::{
         fs::{create_dir_all, File},
         io::prelude::*,
@@ -186,7 +525,7 @@ MIIISIJEEE
 MMMISSJEEE"#;
         file.write_all(data.as_bytes()).unwrap();
         let lines = read_lines(file_path).unwrap();
-        let result = calculate_part_one(lines).unwrap();
+        let result = calculate_part_one(lines, &default_options(), false).unwrap();
 
         assert_eq!(result, 1930);
     }
@@ -209,8 +548,149 @@ MIIISIJEEE
 MMMISSJEEE"#;
         file.write_all(data.as_bytes()).unwrap();
         let lines = read_lines(file_path).unwrap();
-        let result = calculate_part_two(lines).unwrap();
+        let result = calculate_part_two(lines, &default_options(), false).unwrap();
 
         assert_eq!(result, 1206);
     }
+
+    #[test]
+    fn test_label_regions() {
+        let base = tempdir().unwrap().path().to_path_buf();
+        create_dir_all(&base).unwrap();
+        let file_path = base.join("data.txt");
+        let mut file = File::create(&file_path).unwrap();
+        let data = r#"OOOOO
+OXOXO
+OOOOO
+OXOXO
+OOOOO"#;
+        file.write_all(data.as_bytes()).unwrap();
+        let lines = read_lines(file_path).unwrap();
+        let grid = build_twod_vec(lines).unwrap();
+        let result = label_regions(&grid, Connectivity::Four);
+
+        assert_eq!(result[0], vec![0, 0, 0, 0, 0]);
+        assert_eq!(result[1], vec![0, 1, 0, 2, 0]);
+        assert_eq!(result[3], vec![0, 3, 0, 4, 0]);
+    }
+
+    fn default_options() -> Options {
+        Options::new(Connectivity::Four, None, None).unwrap()
+    }
+
+    #[test]
+    fn test_analyze_regions() {
+        let grid = grid_from_str("OOOOO\nOXOXO\nOOOOO\nOXOXO\nOOOOO");
+        let regions = RegionAnalyzer::new(&grid, Connectivity::Four).regions();
+        let outer = &regions[0];
+
+        assert_eq!(regions.len(), 5);
+        assert_eq!(outer.plant, 'O');
+        assert_eq!(outer.area(), 21);
+        assert_eq!(outer.perimeter, 36);
+        assert_eq!(outer.sides, 20);
+        assert_eq!(outer.holes, 4);
+        assert_eq!(
+            outer.bounds,
+            Bounds {
+                top: 0,
+                left: 0,
+                bottom: 4,
+                right: 4
+            }
+        );
+        assert_eq!(regions[1].cells, vec![(1, 1)]);
+        assert_eq!((regions[1].sides, regions[1].holes), (4, 0));
+    }
+
+    #[test]
+    fn test_sides_with_enclosed_regions() {
+        let grid = grid_from_str("AAAAAA\nAAABBA\nAAABBA\nABBAAA\nABBAAA\nAAAAAA");
+        let regions = RegionAnalyzer::new(&grid, Connectivity::Four).regions();
+        let bulk = regions
+            .iter()
+            .map(|region| PricingModel::Sides.price(region))
+            .sum::<usize>();
+
+        assert_eq!(bulk, 368);
+        // The two B pockets touch at a corner, which joins them into one hole.
+        assert_eq!(regions[0].holes, 1);
+        assert_eq!(regions[0].sides, 12);
+    }
+
+    #[test]
+    fn test_eight_connectivity() {
+        let grid = grid_from_str("AB\nBA");
+        let four = RegionAnalyzer::new(&grid, Connectivity::Four).regions();
+        let eight = RegionAnalyzer::new(&grid, Connectivity::Eight).regions();
+
+        assert_eq!(four.len(), 4);
+        assert_eq!(eight.len(), 2);
+        assert_eq!(eight[0].cells, vec![(0, 0), (1, 1)]);
+        assert_eq!((eight[0].perimeter, eight[0].sides), (8, 8));
+    }
+
+    #[test]
+    fn test_holes_depend_on_connectivity() {
+        let grid = grid_from_str("BAB\nABA\nBAB");
+        let four = RegionAnalyzer::new(&grid, Connectivity::Four).regions();
+        let eight = RegionAnalyzer::new(&grid, Connectivity::Eight).regions();
+
+        assert!(four.iter().all(|region| region.holes == 0));
+        assert_eq!(eight[0].plant, 'B');
+        assert_eq!(eight[0].holes, 0);
+        assert_eq!(eight[1].plant, 'A');
+        assert_eq!(eight[1].holes, 1);
+    }
+
+    #[test]
+    fn test_pricing_models() {
+        let grid = grid_from_str("OOOOO\nOXOXO\nOOOOO\nOXOXO\nOOOOO");
+        let regions = RegionAnalyzer::new(&grid, Connectivity::Four).regions();
+        let table = PriceTable::default();
+        let total = |model| Breakdown::new(&regions, model, &table).total();
+
+        assert_eq!(total(PricingModel::Perimeter), 772);
+        assert_eq!(total(PricingModel::Sides), 436);
+        assert_eq!(total(PricingModel::Corners), 36);
+    }
+
+    #[test]
+    fn test_price_table() {
+        let grid = grid_from_str("OOOOO\nOXOXO\nOOOOO\nOXOXO\nOOOOO");
+        let regions = RegionAnalyzer::new(&grid, Connectivity::Four).regions();
+        let table = PriceTable::parse("# premium plants\nX 2\n\nZ 5").unwrap();
+        let breakdown = Breakdown::new(&regions, PricingModel::Perimeter, &table);
+
+        assert_eq!(breakdown.total(), 788);
+        assert_eq!(
+            breakdown.plants[&'X'],
+            PlantCost {
+                regions: 4,
+                area: 4,
+                price: 32
+            }
+        );
+        assert_eq!(
+            breakdown.to_string(),
+            "O: 1 regions, area 21, price 756\nX: 4 regions, area 4, price 32\n"
+        );
+    }
+
+    #[test]
+    fn test_price_table_file() {
+        let base = tempdir().unwrap().path().to_path_buf();
+        create_dir_all(&base).unwrap();
+        let file_path = base.join("prices.txt");
+        let mut file = File::create(&file_path).unwrap();
+        file.write_all(b"A 3\nB x").unwrap();
+        let result = Options::new(Connectivity::Four, None, Some(&file_path));
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Invalid price table entry on line 2: B x"
+        );
+        assert_eq!(PriceTable::parse("A 3").unwrap().multiplier('A'), 3);
+        assert!(PriceTable::parse("AB 3").is_err());
+    }
 }
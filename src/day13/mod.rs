@@ -1,156 +1,444 @@
 use std::fs::read_to_string;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use regex::Regex;
 
-use crate::{utils::build_data_file_path, Day};
+use crate::{
+    answer::{self, Answer},
+    utils::{build_data_file_path, math::extended_gcd},
+    Day,
+};
 
-#[derive(Debug, Hash)]
+const MAX_PRESSES: i128 = 100;
+const PRIZE_OFFSET: i128 = 10000000000000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Position {
-    x: isize,
-    y: isize,
+    x: i128,
+    y: i128,
 }
 
 impl Position {
-    fn new(x: isize, y: isize) -> Self {
+    fn new(x: i128, y: i128) -> Self {
         Self { x, y }
     }
+
+    fn offset(&self, by: i128) -> Result<Self> {
+        self.x
+            .checked_add(by)
+            .zip(self.y.checked_add(by))
+            .map(|(x, y)| Self::new(x, y))
+            .ok_or_else(|| anyhow!("Prize at {self:?} overflowed when moved by {by}"))
+    }
+
+    /// What is left of this position after pressing `button` `times` times.
+    fn minus(&self, button: &Position, times: i128) -> Result<Self> {
+        let x = button
+            .x
+            .checked_mul(times)
+            .and_then(|x| self.x.checked_sub(x));
+        let y = button
+            .y
+            .checked_mul(times)
+            .and_then(|y| self.y.checked_sub(y));
+
+        x.zip(y)
+            .map(|(x, y)| Self::new(x, y))
+            .ok_or_else(|| anyhow!("{self:?} overflowed after {times} presses of {button:?}"))
+    }
+
+    /// The z component of the cross product, which is zero for parallel positions.
+    fn cross(&self, other: &Position) -> Result<i128> {
+        self.x
+            .checked_mul(other.y)
+            .zip(self.y.checked_mul(other.x))
+            .and_then(|(left, right)| left.checked_sub(right))
+            .ok_or_else(|| anyhow!("Cross product of {self:?} and {other:?} overflowed"))
+    }
 }
 
-#[derive(Debug, Hash)]
-struct Game {
-    button_a: Position,
-    button_b: Position,
+/// Token cost of each button, in the order the buttons are listed on a machine.
+#[derive(Debug, Clone)]
+pub struct Costs {
+    buttons: Vec<i128>,
+}
+
+impl Costs {
+    pub fn new(buttons: Vec<i128>) -> Self {
+        Self { buttons }
+    }
+
+    fn for_machine(&self, machine: &Machine) -> Result<&[i128]> {
+        let count = machine.buttons.len();
+        self.buttons.get(..count).ok_or_else(|| {
+            anyhow!(
+                "Only {} costs given for {count} buttons",
+                self.buttons.len()
+            )
+        })
+    }
+
+    fn tokens(&self, presses: &[i128]) -> Result<Answer> {
+        let tokens = presses
+            .iter()
+            .zip(&self.buttons)
+            .try_fold(0_i128, |total, (times, cost)| {
+                total.checked_add(times.checked_mul(*cost)?)
+            })
+            .ok_or_else(|| anyhow!("Token cost of {presses:?} presses overflowed"))?;
+
+        answer::from_signed(tokens)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Machine {
+    buttons: Vec<Position>,
     prize: Position,
 }
 
-impl Game {
-    fn new(button_a: Position, button_b: Position, prize: Position) -> Self {
-        Self {
-            button_a,
-            button_b,
-            prize,
+impl Machine {
+    fn new(buttons: Vec<Position>, prize: Position) -> Self {
+        Self { buttons, prize }
+    }
+
+    /// The cheapest non-negative number of presses per button that lands exactly on the prize,
+    /// if there is one. Two buttons are solved exactly. With more, every button but the last two
+    /// is tried over its possible press counts and the last two are solved exactly for what is
+    /// left, skipping branches that already cost more than the best answer so far.
+    fn solve(&self, costs: &Costs, max_presses: Option<i128>) -> Result<Option<Vec<i128>>> {
+        let costs = costs.for_machine(self)?;
+
+        match self.buttons.len() {
+            0 => Ok((self.prize == Position::new(0, 0)).then(Vec::new)),
+            1 => Ok(solve_single(&self.buttons[0], &self.prize, max_presses)?.map(|p| vec![p])),
+            count => {
+                let bounds = (0..count - 2)
+                    .map(|index| {
+                        self.press_bound(index, max_presses).ok_or_else(|| {
+                            anyhow!("Button {} needs a press limit to search", index + 1)
+                        })
+                    })
+                    .collect::<Result<Vec<i128>>>()?;
+                let mut search = Search {
+                    machine: self,
+                    costs,
+                    bounds: &bounds,
+                    max_presses,
+                    presses: Vec::with_capacity(count),
+                    best: None,
+                };
+                search.run(self.prize, 0)?;
+
+                Ok(search.best.map(|(_, presses)| presses))
+            }
         }
     }
 
-    fn calculate_times_b(&self) -> f64 {
-        (self.prize.y as f64 * self.button_a.x as f64
-            - self.prize.x as f64 * self.button_a.y as f64)
-            / (self.button_b.y as f64 * self.button_a.x as f64
-                - self.button_b.x as f64 * self.button_a.y as f64)
+    /// The most a button can be pressed before it overshoots the prize on an axis that no button
+    /// moves backwards along, capped by `max_presses`.
+    fn press_bound(&self, index: usize, max_presses: Option<i128>) -> Option<i128> {
+        let button = &self.buttons[index];
+        let axes = [
+            (
+                button.x,
+                self.prize.x,
+                self.buttons.iter().all(|b| b.x >= 0),
+            ),
+            (
+                button.y,
+                self.prize.y,
+                self.buttons.iter().all(|b| b.y >= 0),
+            ),
+        ];
+
+        axes.into_iter()
+            .filter(|&(step, _, forwards_only)| forwards_only && step > 0)
+            .map(|(step, target, _)| target.max(-1) / step)
+            .chain(max_presses)
+            .min()
     }
+}
+
+/// Branch and bound over the press counts of every button before the last two.
+#[derive(Debug)]
+struct Search<'a> {
+    machine: &'a Machine,
+    costs: &'a [i128],
+    bounds: &'a [i128],
+    max_presses: Option<i128>,
+    presses: Vec<i128>,
+    best: Option<(i128, Vec<i128>)>,
+}
 
-    fn calculate_times_a(&self) -> f64 {
-        (self.prize.x as f64 - self.button_b.x as f64 * self.calculate_times_b())
-            / self.button_a.x as f64
+impl Search<'_> {
+    fn run(&mut self, remaining: Position, spent: i128) -> Result<()> {
+        let index = self.presses.len();
+        let buttons = &self.machine.buttons;
+
+        if index == self.bounds.len() {
+            let (a, b) = (&buttons[index], &buttons[index + 1]);
+            let pair_costs = (self.costs[index], self.costs[index + 1]);
+            if let Some((times_a, times_b)) =
+                solve_pair(a, b, &remaining, pair_costs, self.max_presses)?
+            {
+                let cost = add_cost(spent, times_a, pair_costs.0)
+                    .and_then(|cost| add_cost(cost, times_b, pair_costs.1))?;
+                if self.best.as_ref().is_none_or(|(best, _)| cost < *best) {
+                    let mut presses = self.presses.clone();
+                    presses.extend([times_a, times_b]);
+                    self.best = Some((cost, presses));
+                }
+            }
+            return Ok(());
+        }
+
+        let prune = self.costs.iter().all(|&cost| cost >= 0);
+        for times in 0..=self.bounds[index] {
+            let cost = add_cost(spent, times, self.costs[index])?;
+            if prune && self.best.as_ref().is_some_and(|(best, _)| cost >= *best) {
+                break;
+            }
+
+            self.presses.push(times);
+            self.run(remaining.minus(&buttons[index], times)?, cost)?;
+            self.presses.pop();
+        }
+
+        Ok(())
     }
 }
 
-pub fn puzzle1() {
-    let file_path = build_data_file_path(&Day::Day13, "data.txt").unwrap();
-    let data = read_to_string(file_path).unwrap();
-    let result = calculate_part_one(&data).unwrap();
+/// The tokens spent after pressing a button costing `cost` another `times` times.
+fn add_cost(spent: i128, times: i128, cost: i128) -> Result<i128> {
+    times
+        .checked_mul(cost)
+        .and_then(|tokens| spent.checked_add(tokens))
+        .ok_or_else(|| anyhow!("Token cost overflowed after {times} presses costing {cost}"))
+}
+
+fn solve_single(
+    button: &Position,
+    prize: &Position,
+    max_presses: Option<i128>,
+) -> Result<Option<i128>> {
+    let step = if button.x != 0 { button.x } else { button.y };
+    let target = if button.x != 0 { prize.x } else { prize.y };
+    if step == 0 {
+        return Ok((prize.x == 0 && prize.y == 0).then_some(0));
+    }
+
+    if target % step != 0 {
+        return Ok(None);
+    }
+    let times = target / step;
+    let in_range = times >= 0 && max_presses.is_none_or(|max| times <= max);
+
+    Ok((in_range && prize.minus(button, times)? == Position::new(0, 0)).then_some(times))
+}
+
+/// The cheapest number of presses of two buttons that lands exactly on the prize, if there is
+/// one.
+fn solve_pair(
+    a: &Position,
+    b: &Position,
+    prize: &Position,
+    costs: (i128, i128),
+    max_presses: Option<i128>,
+) -> Result<Option<(i128, i128)>> {
+    let determinant = a.cross(b)?;
+
+    let presses = if determinant != 0 {
+        let times_a = prize.cross(b)?;
+        let times_b = a.cross(prize)?;
+        if times_a % determinant != 0 || times_b % determinant != 0 {
+            return Ok(None);
+        }
+        (times_a / determinant, times_b / determinant)
+    } else {
+        match solve_collinear(a, b, prize, costs, max_presses) {
+            Some(presses) => presses,
+            None => return Ok(None),
+        }
+    };
+
+    let in_range = |times: i128| times >= 0 && max_presses.is_none_or(|max| times <= max);
+    if !in_range(presses.0) || !in_range(presses.1) {
+        return Ok(None);
+    }
+    let lands = prize.minus(a, presses.0)?.minus(b, presses.1)? == Position::new(0, 0);
+
+    Ok(lands.then_some(presses))
+}
 
-    println!("{result}");
+/// With parallel buttons there is a whole line of solutions to `a * u + b * v = w` along one
+/// axis. The token cost changes linearly along that line, so the cheapest solution sits at one
+/// end of the range where both press counts stay valid.
+fn solve_collinear(
+    a: &Position,
+    b: &Position,
+    prize: &Position,
+    costs: (i128, i128),
+    max_presses: Option<i128>,
+) -> Option<(i128, i128)> {
+    let (u, v, w) = if a.x != 0 || b.x != 0 {
+        (a.x, b.x, prize.x)
+    } else {
+        (a.y, b.y, prize.y)
+    };
+
+    if u == 0 && v == 0 {
+        return (w == 0).then_some((0, 0));
+    }
+
+    let (gcd, x0, y0) = extended_gcd(u, v);
+    if w % gcd != 0 {
+        return None;
+    }
+
+    // Every solution is (a0 + k * step_a, b0 - k * step_b) for some integer k.
+    let (a0, b0) = (x0 * (w / gcd), y0 * (w / gcd));
+    let (step_a, step_b) = (v / gcd, u / gcd);
+    let mut range = (None, None);
+    constrain(&mut range, step_a, a0, max_presses)?;
+    constrain(&mut range, -step_b, b0, max_presses)?;
+
+    let slope = costs.0 * step_a - costs.1 * step_b;
+    let k = if slope > 0 {
+        range.0?
+    } else if slope < 0 {
+        range.1?
+    } else {
+        range.0.or(range.1).unwrap_or(0)
+    };
+
+    Some((a0 + k * step_a, b0 - k * step_b))
 }
 
-pub fn puzzle2() {
+pub fn puzzle1(costs: &Costs, max_presses: Option<i128>) {
     let file_path = build_data_file_path(&Day::Day13, "data.txt").unwrap();
     let data = read_to_string(file_path).unwrap();
-    let result = calculate_part_two(&data).unwrap();
+    answer::report(calculate_part_one(&data, costs, max_presses));
+}
 
-    println!("{result}");
+pub fn puzzle2(costs: &Costs, max_presses: Option<i128>) {
+    let file_path = build_data_file_path(&Day::Day13, "data.txt").unwrap();
+    let data = read_to_string(file_path).unwrap();
+    answer::report(calculate_part_two(&data, costs, max_presses));
 }
 
-fn calculate_part_one(data: &str) -> Result<usize> {
-    let mut total = 0;
-    let games = data.split("\n\n").collect::<Vec<&str>>();
+fn calculate_part_one(data: &str, costs: &Costs, max_presses: Option<i128>) -> Result<Answer> {
+    let mut total = Answer::default();
+    let max_presses = max_presses.or(Some(MAX_PRESSES));
 
-    for game in games {
-        let game_parts = build_game(game).unwrap();
-        let times_b = game_parts.calculate_times_b();
-        let times_a = game_parts.calculate_times_a();
+    for machine in data.trim().split("\n\n") {
+        let machine = build_machine(machine)?;
 
-        if times_a == times_a.floor()
-            && times_b == times_b.floor()
-            && (0.0..=100.0).contains(&times_a)
-            && (0.0..=100.0).contains(&times_b)
-        {
-            total += times_a as usize * 3 + times_b as usize;
+        if let Some(presses) = machine.solve(costs, max_presses)? {
+            total = total.checked_add(&costs.tokens(&presses)?)?;
         }
     }
 
     Ok(total)
 }
 
-fn calculate_part_two(data: &str) -> Result<usize> {
-    let mut total = 0;
-    let games = data.split("\n\n").collect::<Vec<&str>>();
+fn calculate_part_two(data: &str, costs: &Costs, max_presses: Option<i128>) -> Result<Answer> {
+    let mut total = Answer::default();
 
-    for game in games {
-        let mut game_parts = build_game(game).unwrap();
-        game_parts.prize.x += 10000000000000;
-        game_parts.prize.y += 10000000000000;
-
-        let times_b = game_parts.calculate_times_b();
-        let times_a = game_parts.calculate_times_a();
+    for machine in data.trim().split("\n\n") {
+        let mut machine = build_machine(machine)?;
+        machine.prize = machine.prize.offset(PRIZE_OFFSET)?;
+        if machine.buttons.len() > 2 && max_presses.is_none() {
+            return Err(anyhow!(
+                "Machines with more than two buttons need a press limit in part two"
+            ));
+        }
 
-        if times_a == times_a.floor() && times_b == times_b.floor() {
-            total += times_a as usize * 3 + times_b as usize;
+        if let Some(presses) = machine.solve(costs, max_presses)? {
+            total = total.checked_add(&costs.tokens(&presses)?)?;
         }
     }
 
     Ok(total)
 }
 
-fn build_game(game_str: &str) -> Result<Game> {
-    let parts = game_str.split("\n").collect::<Vec<&str>>();
-    let rex = Regex::new(r"X\+\d+").unwrap();
-    let rey = Regex::new(r"Y\+\d+").unwrap();
-    let re_prizex = Regex::new(r"X=\d+").unwrap();
-    let re_prizey = Regex::new(r"Y=\d+").unwrap();
-    let button_a_x = rex
-        .find_iter(parts[0])
-        .next()
-        .map(|x| x.as_str().split_once('+').unwrap())
-        .and_then(|(_, num)| num.parse::<isize>().ok())
-        .unwrap();
-    let button_a_y = rey
-        .find_iter(parts[0])
-        .next()
-        .map(|x| x.as_str().split_once('+').unwrap())
-        .and_then(|(_, num)| num.parse::<isize>().ok())
-        .unwrap();
-    let button_a = Position::new(button_a_x, button_a_y);
-    let button_b_x = rex
-        .find_iter(parts[1])
-        .next()
-        .map(|x| x.as_str().split_once('+').unwrap())
-        .and_then(|(_, num)| num.parse::<isize>().ok())
-        .unwrap();
-    let button_b_y = rey
-        .find_iter(parts[1])
-        .next()
-        .map(|x| x.as_str().split_once('+').unwrap())
-        .and_then(|(_, num)| num.parse::<isize>().ok())
-        .unwrap();
-    let button_b = Position::new(button_b_x, button_b_y);
-    let prize_x = re_prizex
-        .find_iter(parts[2])
-        .next()
-        .map(|x| x.as_str().split_once('=').unwrap())
-        .and_then(|(_, num)| num.parse::<isize>().ok())
-        .unwrap();
-    let prize_y = re_prizey
-        .find_iter(parts[2])
-        .next()
-        .map(|x| x.as_str().split_once('=').unwrap())
-        .and_then(|(_, num)| num.parse::<isize>().ok())
-        .unwrap();
-    let prize = Position::new(prize_x, prize_y);
-    let game = Game::new(button_a, button_b, prize);
+/// Narrows the range of k so that `offset + k * coefficient` stays between 0 and `max`. Returns
+/// `None` when no k can satisfy the bounds.
+fn constrain(
+    range: &mut (Option<i128>, Option<i128>),
+    coefficient: i128,
+    offset: i128,
+    max: Option<i128>,
+) -> Option<()> {
+    if coefficient == 0 {
+        let in_range = offset >= 0 && max.is_none_or(|max| offset <= max);
+        return in_range.then_some(());
+    }
+
+    let (low, high) = if coefficient > 0 {
+        (
+            Some(ceil_div(-offset, coefficient)),
+            max.map(|max| floor_div(max - offset, coefficient)),
+        )
+    } else {
+        (
+            max.map(|max| ceil_div(max - offset, coefficient)),
+            Some(floor_div(-offset, coefficient)),
+        )
+    };
+
+    if let Some(low) = low {
+        range.0 = Some(range.0.map_or(low, |current| current.max(low)));
+    }
+    if let Some(high) = high {
+        range.1 = Some(range.1.map_or(high, |current| current.min(high)));
+    }
 
-    Ok(game)
+    match *range {
+        (Some(low), Some(high)) if low > high => None,
+        _ => Some(()),
+    }
+}
+
+fn floor_div(numerator: i128, denominator: i128) -> i128 {
+    let quotient = numerator / denominator;
+    if numerator % denominator != 0 && (numerator < 0) != (denominator < 0) {
+        quotient - 1
+    } else {
+        quotient
+    }
+}
+
+fn ceil_div(numerator: i128, denominator: i128) -> i128 {
+    -floor_div(-numerator, denominator)
+}
+
+/// Reads any number of `Button <label>: X+<dx>, Y+<dy>` lines followed by a
+/// `Prize: X=<x>, Y=<y>` line.
+fn build_machine(machine_str: &str) -> Result<Machine> {
+    let button_re = Regex::new(r"^Button \w+: X([+-]\d+), Y([+-]\d+)$").unwrap();
+    let prize_re = Regex::new(r"^Prize: X=(-?\d+), Y=(-?\d+)$").unwrap();
+    let mut buttons = Vec::new();
+    let mut lines = machine_str.lines().map(str::trim);
+
+    let prize = loop {
+        let line = lines
+            .next()
+            .ok_or_else(|| anyhow!("Machine has no prize: {machine_str}"))?;
+        if let Some(captures) = button_re.captures(line) {
+            buttons.push(Position::new(captures[1].parse()?, captures[2].parse()?));
+        } else if let Some(captures) = prize_re.captures(line) {
+            break Position::new(captures[1].parse()?, captures[2].parse()?);
+        } else {
+            return Err(anyhow!("Invalid machine line: {line}"));
+        }
+    };
+
+    if let Some(line) = lines.find(|line| !line.is_empty()) {
+        return Err(anyhow!("Unexpected line after the prize: {line}"));
+    }
+
+    Ok(Machine::new(buttons, prize))
 }
 
 #[cfg(test)]
@@ -174,9 +462,9 @@ Prize: X=7870, Y=6450
 Button A: X+69, Y+23
 Button B: X+27, Y+71
 Prize: X=18641, Y=10279"#;
-        let result = calculate_part_one(&data).unwrap();
+        let result = calculate_part_one(data, &Costs::new(vec![3, 1]), None).unwrap();
 
-        assert_eq!(result, 480);
+        assert_eq!(result, Answer::from(480_usize));
     }
 
     #[test]
@@ -196,8 +484,156 @@ Prize: X=7870, Y=6450
 Button A: X+69, Y+23
 Button B: X+27, Y+71
 Prize: X=18641, Y=10279"#;
-        let result = calculate_part_two(&data).unwrap();
+        let result = calculate_part_two(data, &Costs::new(vec![3, 1]), None).unwrap();
+
+        assert_eq!(result, Answer::from(875318608908_usize));
+    }
+
+    #[test]
+    fn test_solve() {
+        let machine =
+            build_machine("Button A: X+94, Y+34\nButton B: X+22, Y+67\nPrize: X=8400, Y=5400")
+                .unwrap();
+
+        assert_eq!(
+            machine.solve(&Costs::new(vec![3, 1]), Some(100)).unwrap(),
+            Some(vec![80, 40])
+        );
+        assert_eq!(
+            machine.solve(&Costs::new(vec![3, 1]), Some(50)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_solve_not_divisible() {
+        let machine =
+            build_machine("Button A: X+26, Y+66\nButton B: X+67, Y+21\nPrize: X=12748, Y=12176")
+                .unwrap();
+
+        assert_eq!(machine.solve(&Costs::new(vec![3, 1]), None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_solve_collinear() {
+        let machine =
+            build_machine("Button A: X+2, Y+2\nButton B: X+1, Y+1\nPrize: X=11, Y=11").unwrap();
+
+        assert_eq!(
+            machine.solve(&Costs::new(vec![3, 1]), None).unwrap(),
+            Some(vec![0, 11])
+        );
+        assert_eq!(
+            machine.solve(&Costs::new(vec![1, 1]), None).unwrap(),
+            Some(vec![5, 1])
+        );
+        assert_eq!(
+            machine.solve(&Costs::new(vec![1, 1]), Some(4)).unwrap(),
+            Some(vec![4, 3])
+        );
+        assert_eq!(
+            machine.solve(&Costs::new(vec![1, 1]), Some(2)).unwrap(),
+            None
+        );
+    }
 
-        assert_eq!(result, 875318608908);
+    #[test]
+    fn test_solve_collinear_off_line() {
+        let machine =
+            build_machine("Button A: X+2, Y+2\nButton B: X+4, Y+4\nPrize: X=7, Y=7").unwrap();
+
+        assert_eq!(machine.solve(&Costs::new(vec![3, 1]), None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_solve_three_buttons() {
+        let machine = build_machine(
+            "Button A: X+2, Y+1\nButton B: X+1, Y+2\nButton C: X+1, Y+1\nPrize: X=6, Y=6",
+        )
+        .unwrap();
+
+        assert_eq!(
+            machine.solve(&Costs::new(vec![3, 3, 1]), None).unwrap(),
+            Some(vec![0, 0, 6])
+        );
+        assert_eq!(
+            machine.solve(&Costs::new(vec![3, 3, 5]), None).unwrap(),
+            Some(vec![2, 2, 0])
+        );
+        assert_eq!(
+            machine.solve(&Costs::new(vec![3, 3, 5]), Some(1)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_solve_single_button() {
+        let machine = build_machine("Button A: X+3, Y+2\nPrize: X=9, Y=6").unwrap();
+
+        assert_eq!(
+            machine.solve(&Costs::new(vec![3]), None).unwrap(),
+            Some(vec![3])
+        );
+        assert_eq!(machine.solve(&Costs::new(vec![3]), Some(2)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_solve_errors() {
+        let machine = build_machine(
+            "Button A: X+2, Y-1\nButton B: X-1, Y+2\nButton C: X+1, Y+1\nPrize: X=6, Y=6",
+        )
+        .unwrap();
+
+        assert_eq!(
+            machine
+                .solve(&Costs::new(vec![3, 1]), None)
+                .unwrap_err()
+                .to_string(),
+            "Only 2 costs given for 3 buttons"
+        );
+        assert_eq!(
+            machine
+                .solve(&Costs::new(vec![3, 1, 1]), None)
+                .unwrap_err()
+                .to_string(),
+            "Button 1 needs a press limit to search"
+        );
+        assert_eq!(
+            machine.solve(&Costs::new(vec![3, 1, 1]), Some(10)).unwrap(),
+            Some(vec![0, 0, 6])
+        );
+    }
+
+    #[test]
+    fn test_overflow_is_an_error() {
+        let button = Position::new(i128::MAX, 1);
+
+        assert!(Position::new(0, 0).minus(&button, 2).is_err());
+        assert!(button.cross(&Position::new(2, 2)).is_err());
+        assert!(add_cost(1, 2, i128::MAX).is_err());
+
+        let machine = build_machine(
+            "Button A: X+2, Y+1\nButton B: X+1, Y+2\nButton C: X+1, Y+1\nPrize: X=6, Y=6",
+        )
+        .unwrap();
+        assert!(machine
+            .solve(&Costs::new(vec![i128::MAX; 3]), None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_part_two_needs_press_limit() {
+        let data = "Button A: X+2, Y+1\nButton B: X+1, Y+2\nButton C: X+1, Y+1\nPrize: X=6, Y=6";
+        let costs = Costs::new(vec![3, 3, 1]);
+
+        assert!(calculate_part_two(data, &costs, None).is_err());
+        assert!(calculate_part_two(data, &costs, Some(10)).is_ok());
+    }
+
+    #[test]
+    fn test_build_machine_errors() {
+        assert!(build_machine("Button A: X+2, Y+1").is_err());
+        assert!(build_machine("Button A: X+2\nPrize: X=6, Y=6").is_err());
+        assert!(build_machine("Prize: X=6, Y=6\nButton A: X+2, Y+1").is_err());
     }
 }
@@ -1,14 +1,15 @@
 use std::{
-    collections::HashMap,
     fs::File,
     io::{BufReader, Lines},
+    path::Path,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use regex::Regex;
 
 use crate::{
-    utils::{build_data_file_path, read_lines},
+    render::{ImageFormat, Renderer},
+    utils::{build_data_file_path, cycle::find_cycle, math::crt, read_lines},
     Day,
 };
 
@@ -23,51 +24,35 @@ impl Grid {
         Self { width, height }
     }
 
-    fn quadrent_one(&self) -> HashMap<&str, (usize, usize)> {
-        let mut quad = HashMap::new();
-        let width = (self.width as f64 / 2.0 - 2.0).ceil() as usize;
-        quad.insert("width", (0, width));
+    /// The quadrant, numbered left to right then top to bottom, that the position falls in. On an
+    /// odd sized axis the middle line belongs to no quadrant.
+    fn quadrant(&self, position: (isize, isize)) -> Option<usize> {
+        let column = Self::half(position.0 as usize, self.width)?;
+        let row = Self::half(position.1 as usize, self.height)?;
 
-        let height = (self.height as f64 / 2.0 - 2.0).ceil() as usize;
-        quad.insert("height", (0, height));
-
-        quad
-    }
-
-    fn quadrent_two(&self) -> HashMap<&str, (usize, usize)> {
-        let mut quad = HashMap::new();
-
-        let width_start = (self.width as f64 / 2.0).ceil() as usize;
-        quad.insert("width", (width_start, self.width - 1));
-
-        let height = (self.height as f64 / 2.0 - 2.0).ceil() as usize;
-        quad.insert("height", (0, height));
-
-        quad
+        Some(row * 2 + column)
     }
 
-    fn quadrent_three(&self) -> HashMap<&str, (usize, usize)> {
-        let mut quad = HashMap::new();
-
-        let width = (self.width as f64 / 2.0 - 2.0).ceil() as usize;
-        quad.insert("width", (0, width));
-
-        let height_start = (self.height as f64 / 2.0).ceil() as usize;
-        quad.insert("height", (height_start, self.height - 1));
-
-        quad
+    fn half(value: usize, size: usize) -> Option<usize> {
+        if value < size / 2 {
+            Some(0)
+        } else if value >= size - size / 2 {
+            Some(1)
+        } else {
+            None
+        }
     }
 
-    fn quadrent_four(&self) -> HashMap<&str, (usize, usize)> {
-        let mut quad = HashMap::new();
-
-        let width_start = (self.width as f64 / 2.0).ceil() as usize;
-        quad.insert("width", (width_start, self.width - 1));
+    fn safety_factor(&self, robots: &[Robot], seconds: usize) -> usize {
+        let mut quadrants = [0; 4];
 
-        let height_start = (self.height as f64 / 2.0).ceil() as usize;
-        quad.insert("height", (height_start, self.height - 1));
+        for robot in robots {
+            if let Some(quadrant) = self.quadrant(robot.position_at(self, seconds)) {
+                quadrants[quadrant] += 1;
+            }
+        }
 
-        quad
+        quadrants.iter().product()
     }
 }
 
@@ -82,139 +67,174 @@ impl Robot {
         Self { position, velocity }
     }
 
-    fn move_robot(&mut self, grid: &Grid, seconds: usize) {
-        for _ in 0..seconds {
-            let mut new_width = self.position.0 + self.velocity.0;
-            let mut new_height = self.position.1 + self.velocity.1;
-
-            if new_width < 0 {
-                new_width += grid.width as isize;
-            }
-
-            if new_width >= grid.width as isize {
-                new_width -= grid.width as isize;
-            }
+    /// Each axis wraps with its own period, so time is reduced per axis before multiplying to
+    /// keep far future lookups from overflowing.
+    fn position_at(&self, grid: &Grid, seconds: usize) -> (isize, isize) {
+        let width = grid.width as isize;
+        let height = grid.height as isize;
+        let x = self.position.0 + self.velocity.0 * (seconds % grid.width) as isize;
+        let y = self.position.1 + self.velocity.1 * (seconds % grid.height) as isize;
 
-            if new_height < 0 {
-                new_height += grid.height as isize;
-            }
-
-            if new_height >= grid.height as isize {
-                new_height -= grid.height as isize;
-            }
+        (x.rem_euclid(width), y.rem_euclid(height))
+    }
 
-            self.position = (new_width, new_height);
-        }
+    fn move_robot(&mut self, grid: &Grid, seconds: usize) {
+        self.position = self.position_at(grid, seconds);
     }
 }
 
-pub fn puzzle1() {
+pub fn puzzle1(render: Option<&Path>, width: usize, height: usize, seconds: usize) {
     let file_path = build_data_file_path(&Day::Day14, "data.txt").unwrap();
-    let lines = read_lines(file_path).unwrap();
-    let grid = Grid::new(101, 103);
-    let seconds = 100;
+    let lines = read_lines(&file_path).unwrap();
+    let grid = Grid::new(width, height);
     let result = calculate_part_one(lines, &grid, seconds).unwrap();
 
+    if let Some(dir) = render {
+        let mut robots = Vec::new();
+        for line in read_lines(&file_path).unwrap().map_while(Result::ok) {
+            let mut robot = get_robot(&line).unwrap();
+            robot.move_robot(&grid, seconds);
+            robots.push(robot);
+        }
+        let cells = draw_robots(&robots, &grid);
+        let renderer = Renderer::new(&cells);
+        renderer.write_ascii(dir, "day14_puzzle1").unwrap();
+        renderer
+            .write_image(dir, "day14_puzzle1", ImageFormat::Pbm)
+            .unwrap();
+    }
+
     println!("{result}");
 }
 
-pub fn puzzle2() {
+pub fn puzzle2(render: Option<&Path>, width: usize, height: usize) {
     let file_path = build_data_file_path(&Day::Day14, "data.txt").unwrap();
     let lines = read_lines(file_path).unwrap();
-    let grid = Grid::new(101, 103);
-    let seconds = 1;
-    let result = calculate_part_two(lines, &grid, seconds).unwrap();
+    let grid = Grid::new(width, height);
+    let result = calculate_part_two(lines, &grid, render).unwrap();
 
     println!("{result}");
 }
 
 fn calculate_part_one(lines: Lines<BufReader<File>>, grid: &Grid, seconds: usize) -> Result<usize> {
-    let mut quadrent_one = 0;
-    let mut quadrent_two = 0;
-    let mut quadrent_three = 0;
-    let mut quadrent_four = 0;
-    let grid_quadrent_one = grid.quadrent_one();
-    let grid_quadrent_two = grid.quadrent_two();
-    let grid_quadrent_three = grid.quadrent_three();
-    let grid_quadrent_four = grid.quadrent_four();
+    let mut robots = Vec::new();
 
     for line in lines.map_while(Result::ok) {
-        let mut robot = get_robot(&line).unwrap();
-        robot.move_robot(grid, seconds);
-        if (grid_quadrent_one.get("width").unwrap().0 as isize
-            ..=grid_quadrent_one.get("width").unwrap().1 as isize)
-            .contains(&robot.position.0)
-            && (grid_quadrent_one.get("height").unwrap().0 as isize
-                ..=grid_quadrent_one.get("height").unwrap().1 as isize)
-                .contains(&robot.position.1)
-        {
-            quadrent_one += 1;
-        } else if (grid_quadrent_two.get("width").unwrap().0 as isize
-            ..=grid_quadrent_two.get("width").unwrap().1 as isize)
-            .contains(&robot.position.0)
-            && (grid_quadrent_two.get("height").unwrap().0 as isize
-                ..=grid_quadrent_two.get("height").unwrap().1 as isize)
-                .contains(&robot.position.1)
-        {
-            quadrent_two += 1;
-        } else if (grid_quadrent_three.get("width").unwrap().0 as isize
-            ..=grid_quadrent_three.get("width").unwrap().1 as isize)
-            .contains(&robot.position.0)
-            && (grid_quadrent_three.get("height").unwrap().0 as isize
-                ..=grid_quadrent_three.get("height").unwrap().1 as isize)
-                .contains(&robot.position.1)
-        {
-            quadrent_three += 1;
-        } else if (grid_quadrent_four.get("width").unwrap().0 as isize
-            ..=grid_quadrent_four.get("width").unwrap().1 as isize)
-            .contains(&robot.position.0)
-            && (grid_quadrent_four.get("height").unwrap().0 as isize
-                ..=grid_quadrent_four.get("height").unwrap().1 as isize)
-                .contains(&robot.position.1)
-        {
-            quadrent_four += 1;
-        }
+        let robot = get_robot(&line).unwrap();
+        robots.push(robot);
     }
 
-    let total = quadrent_one * quadrent_two * quadrent_three * quadrent_four;
-    Ok(total)
+    Ok(grid.safety_factor(&robots, seconds))
 }
 
-/// This code is trash. It doesn't really answer the qustions. I just printed it and search for
-/// something I thought might be the answer.
-fn calculate_part_two(lines: Lines<BufReader<File>>, grid: &Grid, seconds: usize) -> Result<usize> {
+fn calculate_part_two(
+    lines: Lines<BufReader<File>>,
+    grid: &Grid,
+    render: Option<&Path>,
+) -> Result<usize> {
     let mut robots = Vec::new();
-    let mut total = 0;
 
     for line in lines.map_while(Result::ok) {
         let robot = get_robot(&line).unwrap();
         robots.push(robot);
     }
 
-    let mut checking = true;
-
-    while checking {
-        total += 1;
-        let mut tree = vec![vec!['.'; grid.height]; grid.width];
+    let second = find_tree_second(&robots, grid)?;
 
+    if let Some(dir) = render {
         for robot in &mut robots {
-            robot.move_robot(grid, seconds);
-            if (0..tree.len()).contains(&(robot.position.1 as usize))
-                && (0..tree[0].len()).contains(&(robot.position.0 as usize))
-            {
-                tree[robot.position.1 as usize][robot.position.0 as usize] = 'x';
-            }
+            robot.move_robot(grid, second);
         }
+        let tree = draw_robots(&robots, grid);
+        let renderer = Renderer::new(&tree);
+        renderer.write_ascii(dir, "day14_puzzle2")?;
+        renderer.write_image(dir, "day14_puzzle2", ImageFormat::Pbm)?;
+    }
+
+    Ok(second)
+}
 
-        println!("{:?}", tree);
-        println!("total: {total}");
+/// Every robot's coordinate along one axis. The axes wrap independently, so each one repeats on
+/// its own, with a period that divides the axis size.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Axis {
+    positions: Vec<isize>,
+    velocities: Vec<isize>,
+    size: isize,
+}
 
-        if total == 10000 {
-            checking = false;
+impl Axis {
+    fn new(robots: &[Robot], coordinate: fn((isize, isize)) -> isize, size: usize) -> Self {
+        Self {
+            positions: robots.iter().map(|r| coordinate(r.position)).collect(),
+            velocities: robots.iter().map(|r| coordinate(r.velocity)).collect(),
+            size: size as isize,
         }
     }
 
-    Ok(total)
+    fn step(&self) -> Option<Axis> {
+        let positions = self
+            .positions
+            .iter()
+            .zip(&self.velocities)
+            .map(|(position, velocity)| (position + velocity).rem_euclid(self.size))
+            .collect();
+
+        Some(Axis {
+            positions,
+            ..self.clone()
+        })
+    }
+
+    /// The first second with the smallest spread, along with the period the axis repeats with.
+    fn tightest_second(&self) -> Result<(usize, usize)> {
+        let cycle = find_cycle(self.clone(), Axis::step)
+            .ok_or_else(|| anyhow!("Robot positions never repeat"))?;
+        let second = std::iter::successors(Some(self.clone()), Axis::step)
+            .take(cycle.tail + cycle.length)
+            .enumerate()
+            .min_by_key(|(_, axis)| spread(&axis.positions))
+            .map_or(0, |(second, _)| second);
+
+        Ok((second, cycle.length))
+    }
+}
+
+/// When the tree appears the robots bunch together on both axes, so the second with the
+/// smallest spread is found for each axis over its detected period and the two are combined.
+fn find_tree_second(robots: &[Robot], grid: &Grid) -> Result<usize> {
+    let (x_second, x_period) = Axis::new(robots, |p| p.0, grid.width).tightest_second()?;
+    let (y_second, y_period) = Axis::new(robots, |p| p.1, grid.height).tightest_second()?;
+
+    let congruences = [
+        (x_second as i128, x_period as i128),
+        (y_second as i128, y_period as i128),
+    ];
+    crt(&congruences)
+        .map(|(second, _)| second as usize)
+        .ok_or_else(|| {
+            anyhow!("No second matches {x_second} mod {x_period} and {y_second} mod {y_period}")
+        })
+}
+
+/// Variance scaled by n², `n * sum(x²) - sum(x)²`, so comparisons stay in integers.
+fn spread(values: &[isize]) -> isize {
+    let count = values.len() as isize;
+    let sum = values.iter().sum::<isize>();
+    let sum_squares = values.iter().map(|v| v * v).sum::<isize>();
+
+    count * sum_squares - sum * sum
+}
+
+/// Lays the robots out as rows of height and columns of width, marking occupied cells with `#`.
+fn draw_robots(robots: &[Robot], grid: &Grid) -> Vec<Vec<char>> {
+    let mut cells = vec![vec!['.'; grid.width]; grid.height];
+
+    for robot in robots {
+        cells[robot.position.1 as usize][robot.position.0 as usize] = '#';
+    }
+
+    cells
 }
 
 fn get_robot(line: &str) -> Result<Robot> {
@@ -268,32 +288,73 @@ mod tests {
         assert_eq!(result, 12);
     }
 
-    /*#[test]
-    fn test_part_two() {
-        let base = tempdir().unwrap().path().to_path_buf();
-        create_dir_all(&base).unwrap();
-        let file_path = base.join("data.txt");
-        let mut file = File::create(&file_path).unwrap();
-        let data = r#"p=0,4 v=3,-3
-        p=6,3 v=-1,-3
-        p=10,3 v=-1,2
-        p=2,0 v=2,-1
-        p=0,0 v=1,3
-        p=3,0 v=-2,-2
-        p=7,6 v=-1,-3
-        p=3,0 v=-1,-2
-        p=9,3 v=2,3
-        p=7,3 v=-1,2
-        p=2,4 v=2,-3
-        p=9,5 v=-3,-3"#;
-        file.write_all(data.as_bytes()).unwrap();
-        let lines = read_lines(file_path).unwrap();
+    #[test]
+    fn test_find_tree_second() {
         let grid = Grid::new(11, 7);
-        let seconds = 100;
-        let result = calculate_part_two(lines, &grid, 100).unwrap();
+        let tree_second = 37;
+        let tree = [
+            (5, 1),
+            (4, 2),
+            (5, 2),
+            (6, 2),
+            (3, 3),
+            (4, 3),
+            (5, 3),
+            (6, 3),
+            (7, 3),
+            (5, 4),
+        ];
+        let velocities = [
+            (1, 2),
+            (-3, 1),
+            (2, -1),
+            (4, 3),
+            (-1, -2),
+            (3, -3),
+            (-2, 2),
+            (5, 1),
+            (-4, -1),
+            (2, 3),
+        ];
+        let robots = tree
+            .iter()
+            .zip(velocities)
+            .map(|(&(x, y), (vx, vy))| {
+                let start = (
+                    (x - vx * tree_second as isize).rem_euclid(grid.width as isize),
+                    (y - vy * tree_second as isize).rem_euclid(grid.height as isize),
+                );
+                Robot::new(start, (vx, vy))
+            })
+            .collect::<Vec<Robot>>();
+
+        assert_eq!(find_tree_second(&robots, &grid).unwrap(), tree_second);
+    }
 
-        assert_eq!(result, 0);
-    }*/
+    #[test]
+    fn test_axis_cycle() {
+        let grid = Grid::new(12, 7);
+        let robots = [
+            Robot::new((0, 4), (3, 2)),
+            Robot::new((6, 3), (-6, -1)),
+            Robot::new((2, 0), (9, 3)),
+        ];
+        let xs = Axis::new(&robots, |p| p.0, grid.width);
+        let (_, period) = xs.tightest_second().unwrap();
+
+        assert_eq!(period, 4);
+        for seconds in [0, 5, 13] {
+            let positions = robots
+                .iter()
+                .map(|robot| robot.position_at(&grid, seconds).0)
+                .collect::<Vec<isize>>();
+            let stepped = std::iter::successors(Some(xs.clone()), Axis::step)
+                .nth(seconds)
+                .unwrap();
+
+            assert_eq!(stepped.positions, positions);
+        }
+    }
 
     #[test]
     fn test_get_robot() {
@@ -313,42 +374,58 @@ mod tests {
     }
 
     #[test]
-    fn test_quadrant_one() {
-        let grid = Grid::new(11, 7);
-        let mut expected = HashMap::new();
-        expected.insert("width", (0, 4));
-        expected.insert("height", (0, 2));
+    fn test_draw_robots() {
+        let grid = Grid::new(3, 2);
+        let robots = vec![Robot::new((0, 0), (1, 1)), Robot::new((2, 1), (1, 1))];
+        let result = draw_robots(&robots, &grid);
 
-        assert_eq!(grid.quadrent_one(), expected);
+        assert_eq!(result, vec![vec!['#', '.', '.'], vec!['.', '.', '#']]);
     }
 
     #[test]
-    fn test_quadrant_two() {
+    fn test_position_at() {
         let grid = Grid::new(11, 7);
-        let mut expected = HashMap::new();
-        expected.insert("width", (6, 10));
-        expected.insert("height", (0, 2));
+        let robot = get_robot("p=2,4 v=2,-3").unwrap();
 
-        assert_eq!(grid.quadrent_two(), expected);
+        assert_eq!(robot.position_at(&grid, 5), (1, 3));
+        assert_eq!(robot.position_at(&grid, 77 * 1_000_000_000 + 5), (1, 3));
     }
 
     #[test]
-    fn test_quadrant_three() {
+    fn test_quadrant_odd_grid() {
         let grid = Grid::new(11, 7);
-        let mut expected = HashMap::new();
-        expected.insert("width", (0, 4));
-        expected.insert("height", (4, 6));
 
-        assert_eq!(grid.quadrent_three(), expected);
+        assert_eq!(grid.quadrant((4, 2)), Some(0));
+        assert_eq!(grid.quadrant((6, 0)), Some(1));
+        assert_eq!(grid.quadrant((0, 4)), Some(2));
+        assert_eq!(grid.quadrant((10, 6)), Some(3));
+        assert_eq!(grid.quadrant((5, 0)), None);
+        assert_eq!(grid.quadrant((0, 3)), None);
     }
 
     #[test]
-    fn test_quadrant_four() {
-        let grid = Grid::new(11, 7);
-        let mut expected = HashMap::new();
-        expected.insert("width", (6, 10));
-        expected.insert("height", (4, 6));
+    fn test_quadrant_even_grid() {
+        let grid = Grid::new(10, 6);
 
-        assert_eq!(grid.quadrent_four(), expected);
+        assert_eq!(grid.quadrant((4, 2)), Some(0));
+        assert_eq!(grid.quadrant((5, 2)), Some(1));
+        assert_eq!(grid.quadrant((4, 3)), Some(2));
+        assert_eq!(grid.quadrant((5, 3)), Some(3));
+    }
+
+    #[test]
+    fn test_safety_factor() {
+        let grid = Grid::new(4, 4);
+        let robots = vec![
+            Robot::new((0, 0), (0, 0)),
+            Robot::new((3, 0), (0, 0)),
+            Robot::new((3, 1), (0, 0)),
+            Robot::new((0, 3), (0, 0)),
+            Robot::new((3, 3), (0, 0)),
+            Robot::new((0, 0), (2, 0)),
+        ];
+
+        assert_eq!(grid.safety_factor(&robots, 0), 2 * 2);
+        assert_eq!(grid.safety_factor(&robots, 1), 3);
     }
 }
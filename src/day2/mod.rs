@@ -1,4 +1,5 @@
 use std::{
+    fmt::{Display, Formatter},
     fs::File,
     io::{BufReader, Lines},
 };
@@ -10,76 +11,219 @@ use crate::{
     Day,
 };
 
-pub fn puzzle1() {
+#[derive(Debug, Clone, Copy)]
+pub struct Rules {
+    min_step: usize,
+    max_step: usize,
+    tolerance: usize,
+}
+
+impl Rules {
+    pub fn new(min_step: usize, max_step: usize, tolerance: usize) -> Self {
+        Self {
+            min_step,
+            max_step,
+            tolerance,
+        }
+    }
+
+    fn with_tolerance(&self, tolerance: usize) -> Self {
+        Self { tolerance, ..*self }
+    }
+
+    fn is_valid_step(&self, from: usize, to: usize, increasing: bool) -> bool {
+        let in_direction = if increasing { to >= from } else { to <= from };
+
+        in_direction && (self.min_step..=self.max_step).contains(&from.abs_diff(to))
+    }
+}
+
+/// Why a report is unsafe before any levels are removed. `index` is the level where the problem
+/// shows up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Failure {
+    DirectionChange { index: usize },
+    StepSize { index: usize, step: usize },
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Failure::DirectionChange { index } => write!(f, "direction changes at level {index}"),
+            Failure::StepSize { index, step } => {
+                write!(f, "step of {step} into level {index} is out of range")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Assessment {
+    safe: bool,
+    removed: Vec<usize>,
+    failure: Option<Failure>,
+}
+
+impl Display for Assessment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.safe, self.failure) {
+            (true, None) => write!(f, "safe"),
+            (true, Some(failure)) => {
+                write!(f, "safe after removing {:?}, {failure}", self.removed)
+            }
+            (false, Some(failure)) => write!(f, "unsafe, {failure}"),
+            (false, None) => write!(f, "unsafe"),
+        }
+    }
+}
+
+pub fn puzzle1(rules: &Rules, details: bool) {
     let file_path = build_data_file_path(&Day::Day2, "data.txt").unwrap();
     let lines = read_lines(file_path).unwrap();
-    let result = calculate_part_one(lines).unwrap();
+    let result = calculate_part_one(lines, rules, details).unwrap();
 
     println!("{result}");
 }
 
-pub fn puzzle2() {
+pub fn puzzle2(rules: &Rules, details: bool) {
     let file_path = build_data_file_path(&Day::Day2, "data.txt").unwrap();
     let lines = read_lines(file_path).unwrap();
-    let result = calculate_part_two(lines).unwrap();
+    let result = calculate_part_two(lines, rules, details).unwrap();
 
     println!("{result}");
 }
 
-fn calculate_part_one(lines: Lines<BufReader<File>>) -> Result<usize> {
-    let mut total = 0;
+fn calculate_part_one(
+    lines: Lines<BufReader<File>>,
+    rules: &Rules,
+    details: bool,
+) -> Result<usize> {
+    let assessments = assess_reports(lines, &rules.with_tolerance(0))?;
 
-    for line in lines.map_while(Result::ok) {
-        let levels = split_whitespace_to_usize(&line);
+    Ok(count_safe(&assessments, details))
+}
+
+fn calculate_part_two(
+    lines: Lines<BufReader<File>>,
+    rules: &Rules,
+    details: bool,
+) -> Result<usize> {
+    let assessments = assess_reports(lines, rules)?;
 
-        if is_valid(&levels) {
-            total += 1;
+    Ok(count_safe(&assessments, details))
+}
+
+fn count_safe(assessments: &[Assessment], details: bool) -> usize {
+    if details {
+        for (index, assessment) in assessments.iter().enumerate() {
+            println!("{}: {assessment}", index + 1);
         }
     }
 
-    Ok(total)
+    assessments
+        .iter()
+        .filter(|assessment| assessment.safe)
+        .count()
 }
 
-fn calculate_part_two(lines: Lines<BufReader<File>>) -> Result<usize> {
-    let mut total = 0;
+fn assess_reports(lines: Lines<BufReader<File>>, rules: &Rules) -> Result<Vec<Assessment>> {
+    let mut assessments = Vec::new();
 
     for line in lines.map_while(Result::ok) {
         let levels = split_whitespace_to_usize(&line);
+        assessments.push(assess(&levels, rules));
+    }
+
+    Ok(assessments)
+}
+
+fn assess(levels: &[usize], rules: &Rules) -> Assessment {
+    let failure = find_failure(levels, rules);
+    if failure.is_none() {
+        return Assessment {
+            safe: true,
+            removed: Vec::new(),
+            failure,
+        };
+    }
 
-        if is_valid(&levels) {
-            total += 1;
+    let removed = [true, false]
+        .iter()
+        .filter_map(|&increasing| dampen(levels, rules, increasing))
+        .min_by_key(|removed| removed.len());
+
+    Assessment {
+        safe: removed.is_some(),
+        removed: removed.unwrap_or_default(),
+        failure,
+    }
+}
+
+/// The first problem in the report. The direction is set by the first pair of levels that differ.
+fn find_failure(levels: &[usize], rules: &Rules) -> Option<Failure> {
+    let increasing = levels
+        .windows(2)
+        .find(|w| w[0] != w[1])
+        .is_none_or(|w| w[1] > w[0]);
+
+    levels.windows(2).enumerate().find_map(|(index, w)| {
+        let changes_direction = if increasing { w[1] < w[0] } else { w[1] > w[0] };
+        if changes_direction {
+            Some(Failure::DirectionChange { index: index + 1 })
+        } else if !rules.is_valid_step(w[0], w[1], increasing) {
+            Some(Failure::StepSize {
+                index: index + 1,
+                step: w[0].abs_diff(w[1]),
+            })
         } else {
-            for i in 0..levels.len() {
-                let reduced = levels
-                    .iter()
-                    .enumerate()
-                    .filter(|&(index, _)| index != i)
-                    .map(|(_, &value)| value)
-                    .collect::<Vec<_>>();
-
-                if is_valid(&reduced) {
-                    total += 1;
-                    break;
-                }
+            None
+        }
+    })
+}
+
+/// Finds the fewest levels to remove, at most `rules.tolerance`, so the rest run in one
+/// direction. `removals[i]` is the fewest removals before level `i` when it is kept, and only the
+/// `tolerance + 1` levels before `i` can precede it, so this runs in O(n * tolerance).
+fn dampen(levels: &[usize], rules: &Rules, increasing: bool) -> Option<Vec<usize>> {
+    let tolerance = rules.tolerance;
+    let mut removals = vec![usize::MAX; levels.len()];
+    let mut previous: Vec<Option<usize>> = vec![None; levels.len()];
+
+    for index in 0..levels.len() {
+        if index <= tolerance {
+            removals[index] = index;
+        }
+
+        for prior in index.saturating_sub(tolerance + 1)..index {
+            if removals[prior] == usize::MAX
+                || !rules.is_valid_step(levels[prior], levels[index], increasing)
+            {
+                continue;
+            }
+
+            let count = removals[prior] + index - prior - 1;
+            if count < removals[index] {
+                removals[index] = count;
+                previous[index] = Some(prior);
             }
         }
     }
 
-    Ok(total)
-}
+    let last = (0..levels.len())
+        .filter(|&index| removals[index] != usize::MAX)
+        .map(|index| (removals[index] + levels.len() - 1 - index, index))
+        .filter(|&(count, _)| count <= tolerance)
+        .min()?
+        .1;
 
-fn is_valid(values: &[usize]) -> bool {
-    if values.len() < 2 {
-        return true;
+    let mut kept = vec![false; levels.len()];
+    let mut current = Some(last);
+    while let Some(index) = current {
+        kept[index] = true;
+        current = previous[index];
     }
-    let is_valid_increasing = values
-        .windows(2)
-        .all(|w| w[1] > w[0] && w[1].abs_diff(w[0]) <= 3);
-    let is_valid_decreasing = values
-        .windows(2)
-        .all(|w| w[1] < w[0] && w[1].abs_diff(w[0]) <= 3);
 
-    is_valid_increasing || is_valid_decreasing
+    Some((0..levels.len()).filter(|&index| !kept[index]).collect())
 }
 
 #[cfg(test)]
@@ -105,7 +249,7 @@ mod tests {
 1 3 6 7 9"#;
         file.write_all(data.as_bytes()).unwrap();
         let lines = read_lines(file_path).unwrap();
-        let result = calculate_part_one(lines).unwrap();
+        let result = calculate_part_one(lines, &Rules::new(1, 3, 1), false).unwrap();
 
         assert_eq!(result, 2);
     }
@@ -124,8 +268,55 @@ mod tests {
 1 3 6 7 9"#;
         file.write_all(data.as_bytes()).unwrap();
         let lines = read_lines(file_path).unwrap();
-        let result = calculate_part_two(lines).unwrap();
+        let result = calculate_part_two(lines, &Rules::new(1, 3, 1), false).unwrap();
 
         assert_eq!(result, 4);
     }
+
+    #[test]
+    fn test_assess() {
+        let rules = Rules::new(1, 3, 1);
+
+        assert_eq!(
+            assess(&[1, 3, 2, 4, 5], &rules),
+            Assessment {
+                safe: true,
+                removed: vec![2],
+                failure: Some(Failure::DirectionChange { index: 2 }),
+            }
+        );
+        assert_eq!(
+            assess(&[1, 2, 7, 8, 9], &rules),
+            Assessment {
+                safe: false,
+                removed: Vec::new(),
+                failure: Some(Failure::StepSize { index: 2, step: 5 }),
+            }
+        );
+    }
+
+    #[test]
+    fn test_assess_removes_first_level() {
+        let rules = Rules::new(1, 3, 1);
+        let result = assess(&[9, 1, 2, 3], &rules);
+
+        assert!(result.safe);
+        assert_eq!(result.removed, vec![0]);
+    }
+
+    #[test]
+    fn test_assess_with_tolerance() {
+        let levels = [1, 9, 2, 8, 3, 4];
+
+        assert!(!assess(&levels, &Rules::new(1, 3, 1)).safe);
+        assert_eq!(assess(&levels, &Rules::new(1, 3, 2)).removed, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_assess_with_step_bounds() {
+        let levels = [1, 5, 9, 13];
+
+        assert!(!assess(&levels, &Rules::new(1, 3, 0)).safe);
+        assert!(assess(&levels, &Rules::new(2, 4, 0)).safe);
+    }
 }
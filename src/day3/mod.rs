@@ -1,10 +1,124 @@
-use std::fs::read_to_string;
+use std::{fmt::Debug, fs::read_to_string};
 
 use anyhow::Result;
-use regex::Regex;
 
 use crate::{utils::build_data_file_path, Day};
 
+const PART_ONE_INSTRUCTIONS: &[InstructionKind] = &[InstructionKind::new("mul", parse_mul)];
+const PART_TWO_INSTRUCTIONS: &[InstructionKind] = &[
+    InstructionKind::new("mul", parse_mul),
+    InstructionKind::new("do", parse_do),
+    InstructionKind::new("don't", parse_dont),
+];
+
+#[derive(Debug)]
+struct Machine {
+    enabled: bool,
+    total: usize,
+}
+
+impl Machine {
+    fn new() -> Self {
+        Self {
+            enabled: true,
+            total: 0,
+        }
+    }
+
+    fn run(&mut self, instructions: &[Box<dyn Instruction>]) -> usize {
+        for instruction in instructions {
+            instruction.execute(self);
+        }
+
+        self.total
+    }
+}
+
+trait Instruction: Debug {
+    fn execute(&self, machine: &mut Machine);
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Mul(usize, usize);
+
+impl Instruction for Mul {
+    fn execute(&self, machine: &mut Machine) {
+        if machine.enabled {
+            machine.total += self.0 * self.1;
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Do;
+
+impl Instruction for Do {
+    fn execute(&self, machine: &mut Machine) {
+        machine.enabled = true;
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Dont;
+
+impl Instruction for Dont {
+    fn execute(&self, machine: &mut Machine) {
+        machine.enabled = false;
+    }
+}
+
+/// An instruction the scanner recognises as `name(arguments)`. `parse` gets the text between the
+/// parentheses and rejects the match by returning `None`.
+struct InstructionKind {
+    name: &'static str,
+    parse: fn(&str) -> Option<Box<dyn Instruction>>,
+}
+
+impl InstructionKind {
+    const fn new(name: &'static str, parse: fn(&str) -> Option<Box<dyn Instruction>>) -> Self {
+        Self { name, parse }
+    }
+}
+
+struct Scanner<'a> {
+    kinds: &'a [InstructionKind],
+}
+
+impl<'a> Scanner<'a> {
+    fn new(kinds: &'a [InstructionKind]) -> Self {
+        Self { kinds }
+    }
+
+    /// Reads the instructions in the order they appear, skipping any corrupted text between them.
+    fn scan(&self, data: &str) -> Vec<Box<dyn Instruction>> {
+        let mut instructions = Vec::new();
+        let mut position = 0;
+
+        while position < data.len() {
+            let rest = &data[position..];
+            match self.match_at(rest) {
+                Some((instruction, length)) => {
+                    instructions.push(instruction);
+                    position += length;
+                }
+                None => position += rest.chars().next().map_or(1, char::len_utf8),
+            }
+        }
+
+        instructions
+    }
+
+    fn match_at(&self, rest: &str) -> Option<(Box<dyn Instruction>, usize)> {
+        self.kinds.iter().find_map(|kind| {
+            let arguments = rest.strip_prefix(kind.name)?.strip_prefix('(')?;
+            let end = arguments.find(')')?;
+            let instruction = (kind.parse)(&arguments[..end])?;
+
+            Some((instruction, kind.name.len() + end + 2))
+        })
+    }
+}
+
 pub fn puzzle1() {
     let file_path = build_data_file_path(&Day::Day3, "data.txt").unwrap();
     let data = read_to_string(file_path).unwrap();
@@ -22,53 +136,41 @@ pub fn puzzle2() {
 }
 
 fn calculate_part_one(data: &str) -> Result<usize> {
-    let mut total = 0;
-    let re = Regex::new(r"mul\(\d{1,3},\d{1,3}\)").unwrap();
-
-    for values in re.find_iter(data) {
-        let values_str = values.as_str();
-        let stripped = values_str.replace("mul(", "").replace(')', "");
-        let nums = stripped
-            .split(",")
-            .map(|s| s.parse::<usize>().unwrap())
-            .collect::<Vec<usize>>();
-        total += nums[0] * nums[1];
-    }
+    let instructions = Scanner::new(PART_ONE_INSTRUCTIONS).scan(data);
 
-    Ok(total)
+    Ok(Machine::new().run(&instructions))
 }
 
 fn calculate_part_two(data: &str) -> Result<usize> {
-    let mut total = 0;
-    let re = Regex::new(r"mul\(\d{1,3},\d{1,3}\)").unwrap();
-
-    let parts = data.split("don't");
-    for (index, part) in parts.enumerate() {
-        if index == 0 {
-            for values in re.find_iter(part) {
-                let values_str = values.as_str();
-                let stripped = values_str.replace("mul(", "").replace(')', "");
-                let nums = stripped
-                    .split(",")
-                    .map(|s| s.parse::<usize>().unwrap())
-                    .collect::<Vec<usize>>();
-                total += nums[0] * nums[1];
-            }
-        } else if let Some(start) = part.find("do") {
-            for values in re.find_iter(&part[start..]) {
-                let values_str = values.as_str();
-                let stripped = values_str.replace("mul(", "").replace(')', "");
-                let nums = stripped
-                    .split(",")
-                    .map(|s| s.parse::<usize>().unwrap())
-                    .collect::<Vec<usize>>();
-                println!("{:?}", nums);
-                total += nums[0] * nums[1];
-            }
-        }
+    let instructions = Scanner::new(PART_TWO_INSTRUCTIONS).scan(data);
+
+    Ok(Machine::new().run(&instructions))
+}
+
+fn parse_mul(arguments: &str) -> Option<Box<dyn Instruction>> {
+    let (left, right) = arguments.split_once(',')?;
+
+    Some(Box::new(Mul(parse_operand(left)?, parse_operand(right)?)))
+}
+
+fn parse_operand(value: &str) -> Option<usize> {
+    if value.is_empty() || value.len() > 3 || !value.chars().all(|c| c.is_ascii_digit()) {
+        return None;
     }
 
-    Ok(total)
+    value.parse::<usize>().ok()
+}
+
+fn parse_do(arguments: &str) -> Option<Box<dyn Instruction>> {
+    arguments
+        .is_empty()
+        .then(|| Box::new(Do) as Box<dyn Instruction>)
+}
+
+fn parse_dont(arguments: &str) -> Option<Box<dyn Instruction>> {
+    arguments
+        .is_empty()
+        .then(|| Box::new(Dont) as Box<dyn Instruction>)
 }
 
 #[cfg(test)]
@@ -90,4 +192,60 @@ mod tests {
 
         assert_eq!(result, 48);
     }
+
+    #[test]
+    fn test_scan() {
+        let data = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        let instructions = Scanner::new(PART_TWO_INSTRUCTIONS).scan(data);
+        let result = instructions
+            .iter()
+            .map(|instruction| format!("{instruction:?}"))
+            .collect::<Vec<String>>();
+
+        assert_eq!(
+            result,
+            vec![
+                "Mul(2, 4)",
+                "Dont",
+                "Mul(5, 5)",
+                "Mul(11, 8)",
+                "Do",
+                "Mul(8, 5)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_part_two_ignores_lookalikes() {
+        let data = "don't()do_not_mul(5,5)mul(2,3)do()mul(1,4)mul(1234,1)";
+        let result = calculate_part_two(data).unwrap();
+
+        assert_eq!(result, 4);
+    }
+
+    #[test]
+    fn test_extra_instruction() {
+        #[derive(Debug)]
+        struct Reset;
+
+        impl Instruction for Reset {
+            fn execute(&self, machine: &mut Machine) {
+                machine.total = 0;
+            }
+        }
+
+        fn parse_reset(arguments: &str) -> Option<Box<dyn Instruction>> {
+            arguments
+                .is_empty()
+                .then(|| Box::new(Reset) as Box<dyn Instruction>)
+        }
+
+        let kinds = [
+            InstructionKind::new("mul", parse_mul),
+            InstructionKind::new("reset", parse_reset),
+        ];
+        let instructions = Scanner::new(&kinds).scan("mul(2,4)reset()mul(3,3)");
+
+        assert_eq!(Machine::new().run(&instructions), 9);
+    }
 }
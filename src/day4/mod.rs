@@ -1,113 +1,316 @@
 use std::{
+    collections::HashSet,
     fs::File,
     io::{BufReader, Lines},
 };
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 
 use crate::{
     utils::{build_data_file_path, build_twod_vec, read_lines},
     Day,
 };
 
-pub fn puzzle1() {
+const DIRECTIONS: [(isize, isize); 8] = [
+    (0, 1),
+    (0, -1),
+    (1, 0),
+    (-1, 0),
+    (1, 1),
+    (-1, -1),
+    (1, -1),
+    (-1, 1),
+];
+const WILDCARD: char = '.';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Let a letter be part of more than one match. When off, the first match found in reading
+    /// order claims its letters.
+    overlapping: bool,
+    /// Continue words off one edge of the grid onto the opposite edge.
+    wrap: bool,
+}
+
+impl SearchOptions {
+    pub fn new(overlapping: bool, wrap: bool) -> Self {
+        Self { overlapping, wrap }
+    }
+}
+
+/// A small 2D pattern where `.` matches any letter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stencil {
+    cells: Vec<Vec<char>>,
+}
+
+impl Stencil {
+    /// Reads rows separated by `/`, padding short rows with wildcards.
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let rows = pattern
+            .split('/')
+            .map(|row| row.chars().collect::<Vec<char>>())
+            .collect::<Vec<Vec<char>>>();
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        if width == 0 {
+            bail!("Stencil pattern is empty");
+        }
+
+        let cells = rows
+            .into_iter()
+            .map(|mut row| {
+                row.resize(width, WILDCARD);
+                row
+            })
+            .collect();
+
+        Ok(Self { cells })
+    }
+
+    fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn width(&self) -> usize {
+        self.cells[0].len()
+    }
+
+    /// Turns the stencil a quarter turn clockwise.
+    fn rotate(&self) -> Self {
+        let cells = (0..self.width())
+            .map(|column| {
+                (0..self.height())
+                    .rev()
+                    .map(|row| self.cells[row][column])
+                    .collect()
+            })
+            .collect();
+
+        Self { cells }
+    }
+
+    fn reflect(&self) -> Self {
+        let cells = self
+            .cells
+            .iter()
+            .map(|row| row.iter().rev().copied().collect())
+            .collect();
+
+        Self { cells }
+    }
+
+    /// The distinct stencils among all four rotations of the stencil and of its mirror image.
+    fn variants(&self) -> Vec<Stencil> {
+        let mut variants: Vec<Stencil> = Vec::new();
+
+        for start in [self.clone(), self.reflect()] {
+            let mut current = start;
+            for _ in 0..4 {
+                let next = current.rotate();
+                if !variants.contains(&current) {
+                    variants.push(current);
+                }
+                current = next;
+            }
+        }
+
+        variants
+    }
+
+    /// The cells covered by the stencil's letters when its top left corner sits at `origin`.
+    fn matches_at(
+        &self,
+        grid: &[Vec<char>],
+        origin: (usize, usize),
+    ) -> Option<Vec<(usize, usize)>> {
+        let mut cells = Vec::new();
+
+        for (row, stencil_row) in self.cells.iter().enumerate() {
+            for (column, &c) in stencil_row.iter().enumerate() {
+                if c == WILDCARD {
+                    continue;
+                }
+
+                let cell = (origin.0 + row, origin.1 + column);
+                if grid[cell.0][cell.1] != c {
+                    return None;
+                }
+                cells.push(cell);
+            }
+        }
+
+        Some(cells)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Match {
+    word: String,
+    start: (usize, usize),
+    direction: (isize, isize),
+}
+
+struct WordSearch<'a> {
+    grid: &'a [Vec<char>],
+    options: SearchOptions,
+}
+
+impl<'a> WordSearch<'a> {
+    fn new(grid: &'a [Vec<char>], options: SearchOptions) -> Self {
+        Self { grid, options }
+    }
+
+    fn find(&self, words: &[String]) -> Vec<Match> {
+        let words = words
+            .iter()
+            .filter(|word| !word.is_empty())
+            .map(|word| (word, word.chars().collect::<Vec<char>>()))
+            .collect::<Vec<(&String, Vec<char>)>>();
+        let mut used = HashSet::new();
+        let mut seen = HashSet::new();
+        let mut matches = Vec::new();
+
+        for row in 0..self.grid.len() {
+            for column in 0..self.grid[row].len() {
+                for direction in DIRECTIONS {
+                    for (word, chars) in &words {
+                        let Some(cells) = self.cells_matching(chars, (row, column), direction)
+                        else {
+                            continue;
+                        };
+
+                        // Single letters and palindromes read the same cells in more than one
+                        // direction, which is still only one match.
+                        let mut key = cells.clone();
+                        key.sort_unstable();
+                        if !seen.insert((word.as_str(), key)) {
+                            continue;
+                        }
+
+                        if !self.options.overlapping {
+                            if cells.iter().any(|cell| used.contains(cell)) {
+                                continue;
+                            }
+                            used.extend(cells);
+                        }
+
+                        matches.push(Match {
+                            word: word.to_string(),
+                            start: (row, column),
+                            direction,
+                        });
+                    }
+                }
+            }
+        }
+
+        matches
+    }
+
+    /// The cells the word covers when read from `start` in `direction`, if every letter matches.
+    /// A wrapped word may not come back around onto a cell it already covers.
+    fn cells_matching(
+        &self,
+        chars: &[char],
+        start: (usize, usize),
+        direction: (isize, isize),
+    ) -> Option<Vec<(usize, usize)>> {
+        let rows = self.grid.len() as isize;
+        let columns = self.grid[0].len() as isize;
+        let mut cells = Vec::new();
+
+        for (i, &c) in chars.iter().enumerate() {
+            let mut row = start.0 as isize + i as isize * direction.0;
+            let mut column = start.1 as isize + i as isize * direction.1;
+
+            if self.options.wrap {
+                row = row.rem_euclid(rows);
+                column = column.rem_euclid(columns);
+            } else if row < 0 || column < 0 || row >= rows || column >= columns {
+                return None;
+            }
+
+            let cell = (row as usize, column as usize);
+            if self.grid[cell.0][cell.1] != c || cells.contains(&cell) {
+                return None;
+            }
+
+            cells.push(cell);
+        }
+
+        Some(cells)
+    }
+}
+
+pub fn puzzle1(words: &[String], options: SearchOptions, details: bool) {
     let file_path = build_data_file_path(&Day::Day4, "data.txt").unwrap();
     let lines = read_lines(file_path).unwrap();
-    let result = calculate_part_one(lines).unwrap();
+    let result = calculate_part_one(lines, words, options, details).unwrap();
 
     println!("{result}");
 }
 
-pub fn puzzle2() {
+pub fn puzzle2(stencil: &Stencil) {
     let file_path = build_data_file_path(&Day::Day4, "data.txt").unwrap();
     let lines = read_lines(file_path).unwrap();
-    let result = calculate_part_two(lines).unwrap();
+    let result = calculate_part_two(lines, stencil).unwrap();
 
     println!("{result}");
 }
 
-fn calculate_part_one(lines: Lines<BufReader<File>>) -> Result<usize> {
-    let mut total = 0;
-    let word_chars = ['X', 'M', 'A', 'S'];
+fn calculate_part_one(
+    lines: Lines<BufReader<File>>,
+    words: &[String],
+    options: SearchOptions,
+    details: bool,
+) -> Result<usize> {
     let grid = build_twod_vec(lines).unwrap();
-    let directions = [
-        (0, 1),
-        (0, -1),
-        (1, 0),
-        (-1, 0),
-        (1, 1),
-        (-1, -1),
-        (1, -1),
-        (-1, 1),
-    ];
-
-    for row in 0..grid.len() {
-        for column in 0..grid[row].len() {
-            if grid[row][column] != 'X' {
-                continue;
-            }
+    let matches = WordSearch::new(&grid, options).find(words);
 
-            for (x, y) in &directions {
-                let mut found = true;
-                for (i, _) in word_chars.iter().enumerate() {
-                    let row_check = row as isize + i as isize * x;
-                    let column_check = column as isize + i as isize * y;
-
-                    if row_check < 0
-                        || column_check < 0
-                        || row_check >= grid.len() as isize
-                        || column_check >= grid.len() as isize
-                        || grid[row_check as usize][column_check as usize] != word_chars[i]
-                    {
-                        found = false;
-                        break;
-                    }
-                }
-
-                if found {
-                    total += 1;
-                }
-            }
+    if details {
+        for found in &matches {
+            println!(
+                "{} at {:?} going {:?}",
+                found.word, found.start, found.direction
+            );
         }
     }
 
-    Ok(total)
+    Ok(matches.len())
 }
 
-fn calculate_part_two(lines: Lines<BufReader<File>>) -> Result<usize> {
-    let mut total = 0;
+fn calculate_part_two(lines: Lines<BufReader<File>>, stencil: &Stencil) -> Result<usize> {
     let grid = build_twod_vec(lines).unwrap();
 
-    for row in 1..grid.len() - 1 {
-        for column in 1..grid[row].len() - 1 {
-            if grid[row][column] == 'A' {
-                let top_left_to_bottom_right =
-                    grid[row - 1][column - 1] == 'M' && grid[row + 1][column + 1] == 'S';
-
-                let top_right_to_bottom_left =
-                    grid[row - 1][column + 1] == 'M' && grid[row + 1][column - 1] == 'S';
+    Ok(find_stencil(&grid, stencil).len())
+}
 
-                let bottom_left_to_top_right =
-                    grid[row + 1][column - 1] == 'M' && grid[row - 1][column + 1] == 'S';
+/// Every placement of the stencil, under any rotation or reflection, keyed by the grid cells its
+/// letters cover. Keying by cells means orientations that look the same are only counted once.
+fn find_stencil(grid: &[Vec<char>], stencil: &Stencil) -> HashSet<Vec<(usize, usize)>> {
+    let mut found = HashSet::new();
 
-                let bottom_right_to_top_left =
-                    grid[row + 1][column + 1] == 'M' && grid[row - 1][column - 1] == 'S';
+    for variant in stencil.variants() {
+        if variant.height() > grid.len() || variant.width() > grid[0].len() {
+            continue;
+        }
 
-                if !(!top_right_to_bottom_left && !bottom_left_to_top_right
-                    || !top_left_to_bottom_right && !bottom_right_to_top_left)
-                {
-                    total += 1;
+        for row in 0..=grid.len() - variant.height() {
+            for column in 0..=grid[0].len() - variant.width() {
+                if let Some(cells) = variant.matches_at(grid, (row, column)) {
+                    found.insert(cells);
                 }
             }
         }
     }
 
-    Ok(total)
+    found
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::grid_from_str;
     use std::{
         fs::{create_dir_all, File},
         io::prelude::*,
@@ -132,7 +335,9 @@ MAMMMXMMMM
 MXMXAXMASX"#;
         file.write_all(data.as_bytes()).unwrap();
         let lines = read_lines(file_path).unwrap();
-        let result = calculate_part_one(lines).unwrap();
+        let words = vec!["XMAS".to_string()];
+        let options = SearchOptions::new(true, false);
+        let result = calculate_part_one(lines, &words, options, false).unwrap();
 
         assert_eq!(result, 18);
     }
@@ -155,8 +360,137 @@ MAMMMXMMMM
 MXMXAXMASX"#;
         file.write_all(data.as_bytes()).unwrap();
         let lines = read_lines(file_path).unwrap();
-        let result = calculate_part_two(lines).unwrap();
+        let stencil = Stencil::parse("M.S/.A./M.S").unwrap();
+        let result = calculate_part_two(lines, &stencil).unwrap();
 
         assert_eq!(result, 9);
     }
+
+    #[test]
+    fn test_find_non_square() {
+        let grid = grid_from_str("..X...\n..M...\nXMASAS");
+        let words = vec!["XMAS".to_string(), "SAS".to_string()];
+        let result = WordSearch::new(&grid, SearchOptions::new(true, false)).find(&words);
+
+        assert_eq!(
+            result,
+            vec![
+                Match {
+                    word: "XMAS".to_string(),
+                    start: (2, 0),
+                    direction: (0, 1),
+                },
+                Match {
+                    word: "SAS".to_string(),
+                    start: (2, 3),
+                    direction: (0, 1),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_without_overlap() {
+        let grid = grid_from_str("XMASAMX");
+        let words = vec!["XMAS".to_string(), "SAMX".to_string()];
+
+        assert_eq!(
+            WordSearch::new(&grid, SearchOptions::new(true, false))
+                .find(&words)
+                .len(),
+            4
+        );
+        assert_eq!(
+            WordSearch::new(&grid, SearchOptions::new(false, false)).find(&words),
+            vec![Match {
+                word: "XMAS".to_string(),
+                start: (0, 0),
+                direction: (0, 1),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_find_with_wrap() {
+        let grid = grid_from_str("ASXM\n....");
+        let words = vec!["XMAS".to_string()];
+
+        assert!(WordSearch::new(&grid, SearchOptions::new(true, false))
+            .find(&words)
+            .is_empty());
+        assert_eq!(
+            WordSearch::new(&grid, SearchOptions::new(true, true)).find(&words),
+            vec![Match {
+                word: "XMAS".to_string(),
+                start: (0, 2),
+                direction: (0, 1),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_find_single_letter_and_palindrome() {
+        let grid = grid_from_str("XAX\n.X.");
+        let options = SearchOptions::new(true, false);
+
+        assert_eq!(
+            WordSearch::new(&grid, options)
+                .find(&["X".to_string()])
+                .len(),
+            3
+        );
+        assert_eq!(
+            WordSearch::new(&grid, options)
+                .find(&["XAX".to_string()])
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_wrap_does_not_reuse_cells() {
+        let grid = grid_from_str("AB");
+        let options = SearchOptions::new(true, true);
+
+        assert_eq!(
+            WordSearch::new(&grid, options)
+                .find(&["AB".to_string()])
+                .len(),
+            1
+        );
+        assert!(WordSearch::new(&grid, options)
+            .find(&["ABA".to_string()])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_stencil_variants() {
+        let x_mas = Stencil::parse("M.S/.A./M.S").unwrap();
+        let glyph = Stencil::parse("AB/C").unwrap();
+
+        assert_eq!(x_mas.variants().len(), 4);
+        assert_eq!(glyph.variants().len(), 8);
+        assert_eq!(
+            glyph.rotate(),
+            Stencil {
+                cells: vec![vec!['C', 'A'], vec!['.', 'B']]
+            }
+        );
+    }
+
+    #[test]
+    fn test_find_stencil_plus() {
+        let grid = grid_from_str(".S...\nMAS..\n.M.M.\n..SAM\n...S.");
+        let stencil = Stencil::parse(".M./MAS/.S.").unwrap();
+
+        assert_eq!(find_stencil(&grid, &stencil).len(), 2);
+    }
+
+    #[test]
+    fn test_find_stencil_symmetric() {
+        let grid = grid_from_str("AAA");
+        let stencil = Stencil::parse("AA").unwrap();
+
+        assert_eq!(find_stencil(&grid, &stencil).len(), 2);
+    }
 }
@@ -1,33 +1,197 @@
 use std::{
-    collections::HashMap,
-    fs::File,
+    cmp::Ordering,
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::{Display, Formatter, Write as _},
+    fs::{write, File},
     io::{BufReader, Lines},
+    path::Path,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
 
 use crate::{
     utils::{build_data_file_path, read_lines},
     Day,
 };
 
-pub fn puzzle1() {
+/// The page ordering rules as a graph, with an edge from each page to the pages that must come
+/// after it.
+#[derive(Debug, Default)]
+struct Rules {
+    pairs: HashSet<(usize, usize)>,
+    successors: HashMap<usize, Vec<usize>>,
+}
+
+impl Rules {
+    fn parse(pairs: Lines<BufReader<File>>) -> Result<Self> {
+        let mut rules = Rules::default();
+
+        for pair in pairs.map_while(Result::ok) {
+            let (before, after) = pair
+                .split_once('|')
+                .ok_or_else(|| anyhow!("Invalid rule: {pair}"))?;
+            let before = before
+                .parse::<usize>()
+                .with_context(|| format!("Invalid rule: {pair}"))?;
+            let after = after
+                .parse::<usize>()
+                .with_context(|| format!("Invalid rule: {pair}"))?;
+            rules.insert(before, after);
+        }
+
+        Ok(rules)
+    }
+
+    fn insert(&mut self, before: usize, after: usize) {
+        if self.pairs.insert((before, after)) {
+            self.successors.entry(before).or_default().push(after);
+        }
+    }
+
+    /// Orders two pages by the rules, usable with `sort_by`. Pages with no rule between them
+    /// compare as equal.
+    fn compare(&self, a: &usize, b: &usize) -> Ordering {
+        if self.pairs.contains(&(*a, *b)) {
+            Ordering::Less
+        } else if self.pairs.contains(&(*b, *a)) {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }
+
+    fn is_correct_order(&self, pages: &[usize]) -> bool {
+        pages.iter().enumerate().all(|(index, page)| {
+            pages[index + 1..]
+                .iter()
+                .all(|later| self.compare(page, later) != Ordering::Greater)
+        })
+    }
+
+    /// Sorts the pages using only the rules between pages in the update. Pages that are free to
+    /// go anywhere keep their original relative order.
+    fn topological_order(&self, pages: &[usize]) -> Result<Vec<usize>, Cycle> {
+        let included = pages.iter().copied().collect::<HashSet<usize>>();
+        let mut incoming: HashMap<usize, usize> = pages.iter().map(|&page| (page, 0)).collect();
+
+        for page in pages {
+            for next in self.successors_within(*page, &included) {
+                *incoming.entry(next).or_default() += 1;
+            }
+        }
+
+        let mut ready = pages
+            .iter()
+            .copied()
+            .filter(|page| incoming[page] == 0)
+            .collect::<VecDeque<usize>>();
+        let mut sorted = Vec::new();
+
+        while let Some(page) = ready.pop_front() {
+            sorted.push(page);
+            for next in self.successors_within(page, &included) {
+                let count = incoming.get_mut(&next).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    ready.push_back(next);
+                }
+            }
+        }
+
+        if sorted.len() < included.len() {
+            let remaining = incoming
+                .into_iter()
+                .filter(|&(_, count)| count > 0)
+                .map(|(page, _)| page)
+                .collect::<HashSet<usize>>();
+            return Err(self.find_cycle(&remaining));
+        }
+
+        Ok(sorted)
+    }
+
+    fn successors_within<'a>(
+        &'a self,
+        page: usize,
+        included: &'a HashSet<usize>,
+    ) -> impl Iterator<Item = usize> + 'a {
+        self.successors
+            .get(&page)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|next| included.contains(next))
+    }
+
+    /// Every page left over by the sort still has a rule putting another leftover page before
+    /// it, so walking those rules backwards must eventually revisit a page.
+    fn find_cycle(&self, remaining: &HashSet<usize>) -> Cycle {
+        let mut sorted_remaining = remaining.iter().copied().collect::<Vec<usize>>();
+        sorted_remaining.sort_unstable();
+        let mut path = vec![sorted_remaining[0]];
+
+        loop {
+            let current = path[path.len() - 1];
+            let previous = sorted_remaining
+                .iter()
+                .copied()
+                .find(|&page| self.pairs.contains(&(page, current)))
+                .unwrap();
+
+            if let Some(start) = path.iter().position(|&page| page == previous) {
+                let mut pages = path.split_off(start);
+                pages.reverse();
+                pages.rotate_right(1);
+                return Cycle { pages };
+            }
+
+            path.push(previous);
+        }
+    }
+}
+
+/// Pages whose rules contradict each other, each one required to come before the next and the
+/// last before the first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cycle {
+    pages: Vec<usize>,
+}
+
+impl Display for Cycle {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for page in &self.pages {
+            write!(f, "{page} -> ")?;
+        }
+
+        write!(f, "{}", self.pages[0])
+    }
+}
+
+pub fn puzzle1(dot: Option<&Path>, update: Option<usize>) {
     let pairs_path = build_data_file_path(&Day::Day5, "pairs.txt").unwrap();
     let file_path = build_data_file_path(&Day::Day5, "data.txt").unwrap();
-    let pairs = read_lines(pairs_path).unwrap();
-    let lines = read_lines(file_path).unwrap();
+    let pairs = read_lines(&pairs_path).unwrap();
+    let lines = read_lines(&file_path).unwrap();
     let result = calculate_part_one(pairs, lines).unwrap();
 
+    if let Some(dot_path) = dot {
+        export_dot(&pairs_path, &file_path, dot_path, update).unwrap();
+    }
+
     println!("{result}");
 }
 
-pub fn puzzle2() {
+pub fn puzzle2(dot: Option<&Path>, update: Option<usize>) {
     let pairs_path = build_data_file_path(&Day::Day5, "pairs.txt").unwrap();
-    let pairs = read_lines(pairs_path).unwrap();
+    let pairs = read_lines(&pairs_path).unwrap();
     let file_path = build_data_file_path(&Day::Day5, "data.txt").unwrap();
-    let lines = read_lines(file_path).unwrap();
+    let lines = read_lines(&file_path).unwrap();
     let result = calculate_part_two(pairs, lines).unwrap();
 
+    if let Some(dot_path) = dot {
+        export_dot(&pairs_path, &file_path, dot_path, update).unwrap();
+    }
+
     println!("{result}");
 }
 
@@ -36,22 +200,12 @@ fn calculate_part_one(
     lines: Lines<BufReader<File>>,
 ) -> Result<usize> {
     let mut total = 0;
-    let mut pairs_vec = Vec::new();
-
-    for pair in pairs.map_while(Result::ok) {
-        let values = pair.split_once('|').unwrap();
-        let x = values.0.parse::<usize>().unwrap();
-        let y = values.1.parse::<usize>().unwrap();
-        pairs_vec.push((x, y));
-    }
+    let rules = Rules::parse(pairs)?;
 
     for line in lines.map_while(Result::ok) {
-        let split = line
-            .split(',')
-            .map(|s| s.parse::<usize>().unwrap())
-            .collect::<Vec<usize>>();
-        if is_correct_order(&pairs_vec, split.clone()) {
-            total += split[split.len() / 2];
+        let pages = parse_update(&line)?;
+        if rules.is_correct_order(&pages) {
+            total += pages[pages.len() / 2];
         }
     }
 
@@ -63,22 +217,14 @@ fn calculate_part_two(
     lines: Lines<BufReader<File>>,
 ) -> Result<usize> {
     let mut total = 0;
-    let mut pairs_vec = Vec::new();
-
-    for pair in pairs.map_while(Result::ok) {
-        let values = pair.split_once('|').unwrap();
-        let x = values.0.parse::<usize>().unwrap();
-        let y = values.1.parse::<usize>().unwrap();
-        pairs_vec.push((x, y));
-    }
+    let rules = Rules::parse(pairs)?;
 
     for line in lines.map_while(Result::ok) {
-        let split = line
-            .split(',')
-            .map(|s| s.parse::<usize>().unwrap())
-            .collect::<Vec<usize>>();
-        if !is_correct_order(&pairs_vec, split.clone()) {
-            let corrected = correct_order(&pairs_vec, &split, &create_pages_map(&split));
+        let pages = parse_update(&line)?;
+        if !rules.is_correct_order(&pages) {
+            let corrected = rules
+                .topological_order(&pages)
+                .map_err(|cycle| anyhow!("Update {line} can't be ordered, rules cycle: {cycle}"))?;
             total += corrected[corrected.len() / 2];
         }
     }
@@ -86,65 +232,115 @@ fn calculate_part_two(
     Ok(total)
 }
 
-fn create_pages_map(pages: &[usize]) -> HashMap<&usize, usize> {
-    let mut page_number_to_index_map = HashMap::new();
+/// Writes the rules to `dot_path`. When `update` is given, only the pages of that update (its
+/// 1-based line number in the data file) and the rules between them are written.
+fn export_dot(
+    pairs_path: &Path,
+    file_path: &Path,
+    dot_path: &Path,
+    update: Option<usize>,
+) -> Result<()> {
+    let rules = Rules::parse(read_lines(pairs_path)?)?;
+    let pages = match update {
+        Some(0) => bail!("Updates are numbered from 1"),
+        Some(number) => {
+            let Some(line) = read_lines(file_path)?.map_while(Result::ok).nth(number - 1) else {
+                bail!("There is no update {number}");
+            };
+            Some(parse_update(&line)?)
+        }
+        None => None,
+    };
 
-    for (index, page) in pages.iter().enumerate() {
-        page_number_to_index_map.insert(page, index);
-    }
+    write(dot_path, to_dot(&rules, pages.as_deref()))?;
 
-    page_number_to_index_map
+    Ok(())
 }
 
-fn is_correct_order(graph: &Vec<(usize, usize)>, pages: Vec<usize>) -> bool {
-    let mut valid = true;
-    for (first, second) in graph {
-        let first_pos = pages.iter().position(|x| x == first);
-        let second_pos = pages.iter().position(|x| x == second);
-        match (first_pos, second_pos) {
-            (Some(first_index), Some(second_index)) => {
-                if first_index > second_index {
-                    valid = false;
-                    break;
-                }
-            }
-            _ => continue,
+/// Renders the rules as a Graphviz digraph. Rules the update breaks are drawn in red and the
+/// rules making up a cycle are drawn bold in orange.
+fn to_dot(rules: &Rules, update: Option<&[usize]>) -> String {
+    let pages = match update {
+        Some(pages) => pages.to_vec(),
+        None => {
+            let mut all = rules
+                .pairs
+                .iter()
+                .flat_map(|&(before, after)| [before, after])
+                .collect::<HashSet<usize>>()
+                .into_iter()
+                .collect::<Vec<usize>>();
+            all.sort_unstable();
+            all
         }
-    }
+    };
+    let positions = pages
+        .iter()
+        .enumerate()
+        .map(|(index, &page)| (page, index))
+        .collect::<HashMap<usize, usize>>();
 
-    valid
-}
+    let mut edges = rules
+        .pairs
+        .iter()
+        .copied()
+        .filter(|(before, after)| positions.contains_key(before) && positions.contains_key(after))
+        .collect::<Vec<(usize, usize)>>();
+    edges.sort_unstable();
 
-fn correct_order(
-    pairs: &Vec<(usize, usize)>,
-    pages: &Vec<usize>,
-    pages_map: &HashMap<&usize, usize>,
-) -> Vec<usize> {
-    let mut page_number_to_index_map = pages_map.clone();
-    let mut sorted = pages.to_owned();
+    let cycle_edges = match rules.topological_order(&pages) {
+        Ok(_) => HashSet::new(),
+        Err(cycle) => cycle
+            .pages
+            .iter()
+            .zip(cycle.pages.iter().cycle().skip(1))
+            .map(|(&before, &after)| (before, after))
+            .collect::<HashSet<(usize, usize)>>(),
+    };
 
-    for (lhs, rhs) in pairs {
-        if !page_number_to_index_map.contains_key(lhs) {
-            continue;
+    let mut dot = String::from("digraph rules {\n");
+    for page in &pages {
+        writeln!(dot, "    {page};").unwrap();
+    }
+    for (before, after) in edges {
+        let mut attributes = Vec::new();
+        if update.is_some() && positions[&before] > positions[&after] {
+            attributes.push("color=red");
+        } else if cycle_edges.contains(&(before, after)) {
+            attributes.push("color=orange");
         }
-        if !page_number_to_index_map.contains_key(rhs) {
-            continue;
+        if cycle_edges.contains(&(before, after)) {
+            attributes.push("style=bold");
         }
-        let lhs_idx = *page_number_to_index_map.get(lhs).unwrap();
-        let rhs_idx = *page_number_to_index_map.get(rhs).unwrap();
 
-        if lhs_idx > rhs_idx {
-            sorted.swap(lhs_idx, rhs_idx);
-            page_number_to_index_map.insert(lhs, rhs_idx);
-            page_number_to_index_map.insert(rhs, lhs_idx);
+        if attributes.is_empty() {
+            writeln!(dot, "    {before} -> {after};").unwrap();
+        } else {
+            writeln!(dot, "    {before} -> {after} [{}];", attributes.join(", ")).unwrap();
         }
     }
+    dot.push_str("}\n");
+
+    dot
+}
 
-    if !is_correct_order(pairs, pages.to_owned()) {
-        return correct_order(pairs, &sorted, &page_number_to_index_map);
+/// Reads the pages of an update. Ordering only makes sense for distinct pages, so an update
+/// that repeats a page is rejected.
+fn parse_update(line: &str) -> Result<Vec<usize>> {
+    let pages = line
+        .split(',')
+        .map(|page| {
+            page.parse::<usize>()
+                .with_context(|| format!("Invalid update: {line}"))
+        })
+        .collect::<Result<Vec<usize>>>()?;
+
+    let mut seen = HashSet::new();
+    if let Some(page) = pages.iter().find(|&&page| !seen.insert(page)) {
+        bail!("Update {line} repeats page {page}");
     }
 
-    sorted
+    Ok(pages)
 }
 
 #[cfg(test)]
@@ -245,4 +441,101 @@ mod tests {
 
         assert_eq!(result, 123);
     }
+
+    fn build_rules(pairs: &[(usize, usize)]) -> Rules {
+        let mut rules = Rules::default();
+        for &(before, after) in pairs {
+            rules.insert(before, after);
+        }
+
+        rules
+    }
+
+    #[test]
+    fn test_compare_with_sort_by() {
+        let rules = build_rules(&[(97, 75), (75, 47), (97, 47), (47, 13), (75, 13), (97, 13)]);
+        let mut pages = vec![13, 47, 97, 75];
+        pages.sort_by(|a, b| rules.compare(a, b));
+
+        assert_eq!(pages, vec![97, 75, 47, 13]);
+        assert!(rules.is_correct_order(&pages));
+    }
+
+    #[test]
+    fn test_topological_order() {
+        let rules = build_rules(&[(1, 2), (2, 3), (9, 1)]);
+
+        assert_eq!(rules.topological_order(&[3, 5, 2, 1]), Ok(vec![5, 1, 2, 3]));
+    }
+
+    #[test]
+    fn test_topological_order_cycle() {
+        let rules = build_rules(&[(1, 2), (2, 3), (3, 1), (3, 4)]);
+        let cycle = rules.topological_order(&[4, 3, 2, 1]).unwrap_err();
+
+        assert_eq!(cycle.pages, vec![1, 2, 3]);
+        assert_eq!(cycle.to_string(), "1 -> 2 -> 3 -> 1");
+    }
+
+    #[test]
+    fn test_topological_order_contradiction() {
+        let rules = build_rules(&[(5, 8), (8, 5)]);
+        let cycle = rules.topological_order(&[8, 5]).unwrap_err();
+
+        assert_eq!(cycle.pages, vec![5, 8]);
+    }
+
+    #[test]
+    fn test_to_dot_update() {
+        let rules = build_rules(&[(1, 2), (2, 3), (1, 3), (9, 1)]);
+        let result = to_dot(&rules, Some(&[2, 1, 3]));
+
+        assert_eq!(
+            result,
+            "digraph rules {\n    2;\n    1;\n    3;\n    1 -> 2 [color=red];\n    1 -> 3;\n    2 -> 3;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_to_dot_cycle() {
+        let rules = build_rules(&[(1, 2), (2, 1), (2, 3)]);
+        let result = to_dot(&rules, None);
+
+        assert_eq!(
+            result,
+            "digraph rules {\n    1;\n    2;\n    3;\n    1 -> 2 [color=orange, style=bold];\n    2 -> 1 [color=orange, style=bold];\n    2 -> 3;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_update() {
+        assert_eq!(parse_update("75,47,61").unwrap(), vec![75, 47, 61]);
+        assert!(parse_update("75,47,75").is_err());
+        assert!(parse_update("75,x").is_err());
+    }
+
+    #[test]
+    fn test_export_dot() {
+        let base = tempdir().unwrap().path().to_path_buf();
+        create_dir_all(&base).unwrap();
+        let pairs_path = base.join("pairs.txt");
+        File::create(&pairs_path)
+            .unwrap()
+            .write_all(b"47|53\n97|47")
+            .unwrap();
+        let file_path = base.join("data.txt");
+        File::create(&file_path)
+            .unwrap()
+            .write_all(b"97,47,53\n53,47")
+            .unwrap();
+        let dot_path = base.join("rules.dot");
+        export_dot(&pairs_path, &file_path, &dot_path, Some(2)).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&dot_path).unwrap(),
+            "digraph rules {\n    53;\n    47;\n    47 -> 53 [color=red];\n}\n"
+        );
+        assert!(export_dot(&pairs_path, &file_path, &dot_path, Some(3)).is_err());
+        assert!(export_dot(&pairs_path, &file_path, &dot_path, Some(0)).is_err());
+    }
 }
@@ -1,205 +1,371 @@
 use std::{
-    collections::HashSet,
     fs::File,
     io::{BufReader, Lines},
-    sync::atomic::{AtomicUsize, Ordering},
+    path::Path,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use rayon::prelude::*;
 
 use crate::{
-    utils::{build_data_file_path, build_twod_vec, read_lines},
+    render::{ImageFormat, Renderer},
+    utils::{build_data_file_path, build_twod_vec, is_in_bounds, read_lines},
     Day,
 };
 
-pub fn puzzle1() {
+const DIRECTIONS: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Guard {
+    position: (usize, usize),
+    direction: usize,
+}
+
+impl Guard {
+    fn new(position: (usize, usize), direction: usize) -> Self {
+        Self {
+            position,
+            direction,
+        }
+    }
+
+    fn turn(&self) -> Self {
+        Self::new(self.position, (self.direction + 1) % DIRECTIONS.len())
+    }
+}
+
+/// Walks the guard through the grid, yielding every (position, facing) state until the guard
+/// leaves the map. Turning in place counts as a step.
+#[derive(Debug)]
+struct Simulator<'a> {
+    grid: &'a [Vec<char>],
+    obstruction: Option<(usize, usize)>,
+    next: Option<Guard>,
+}
+
+impl<'a> Simulator<'a> {
+    fn new(grid: &'a [Vec<char>], start: Guard) -> Self {
+        Self {
+            grid,
+            obstruction: None,
+            next: Some(start),
+        }
+    }
+
+    fn with_obstruction(mut self, obstruction: (usize, usize)) -> Self {
+        self.obstruction = Some(obstruction);
+        self
+    }
+
+    fn is_blocked(&self, position: (usize, usize)) -> bool {
+        self.grid[position.0][position.1] == '#' || self.obstruction == Some(position)
+    }
+
+    fn step(&self, guard: Guard) -> Option<Guard> {
+        let (dx, dy) = DIRECTIONS[guard.direction];
+        let new_x = guard.position.0 as isize + dx;
+        let new_y = guard.position.1 as isize + dy;
+
+        if !is_in_bounds(self.grid, new_x, new_y) {
+            return None;
+        }
+
+        let position = (new_x as usize, new_y as usize);
+        if self.is_blocked(position) {
+            Some(guard.turn())
+        } else {
+            Some(Guard::new(position, guard.direction))
+        }
+    }
+}
+
+impl Iterator for Simulator<'_> {
+    type Item = Guard;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next?;
+        self.next = self.step(current);
+
+        Some(current)
+    }
+}
+
+/// For every cell and direction, how many steps ahead the guard runs into an obstacle or off the
+/// grid, so a whole straight run can be taken at once.
+#[derive(Debug)]
+struct JumpTable {
+    height: usize,
+    width: usize,
+    distances: Vec<[usize; 4]>,
+}
+
+impl JumpTable {
+    fn new(grid: &[Vec<char>]) -> Self {
+        let (height, width) = (grid.len(), grid[0].len());
+        let mut distances = vec![[0; 4]; height * width];
+
+        for (direction, &(dx, dy)) in DIRECTIONS.iter().enumerate() {
+            // Visit cells so the one ahead of each cell is always filled in first.
+            let rows = ordered(height, dx > 0);
+            let cols = ordered(width, dy > 0);
+            for &x in &rows {
+                for &y in &cols {
+                    let (ahead_x, ahead_y) = (x as isize + dx, y as isize + dy);
+                    distances[x * width + y][direction] = if !is_in_bounds(grid, ahead_x, ahead_y)
+                        || grid[ahead_x as usize][ahead_y as usize] == '#'
+                    {
+                        1
+                    } else {
+                        distances[ahead_x as usize * width + ahead_y as usize][direction] + 1
+                    };
+                }
+            }
+        }
+
+        Self {
+            height,
+            width,
+            distances,
+        }
+    }
+
+    /// Moves the guard up to the next obstacle and turns it, or returns `None` when the guard
+    /// walks off the grid. A candidate obstruction is checked against the run instead of being
+    /// written into the grid.
+    fn jump(&self, guard: Guard, obstruction: Option<(usize, usize)>) -> Option<Guard> {
+        let (x, y) = (guard.position.0 as isize, guard.position.1 as isize);
+        let (dx, dy) = DIRECTIONS[guard.direction];
+        let mut distance = self.distances[guard.position.0 * self.width + guard.position.1]
+            [guard.direction] as isize;
+
+        if let Some((ox, oy)) = obstruction {
+            let (ox, oy) = (ox as isize - x, oy as isize - y);
+            let along = match (dx, dy) {
+                (0, _) => (ox == 0).then_some(oy * dy),
+                _ => (oy == 0).then_some(ox * dx),
+            };
+            if let Some(along) = along.filter(|&along| along > 0 && along < distance) {
+                distance = along;
+            }
+        }
+
+        let (end_x, end_y) = (x + dx * distance, y + dy * distance);
+        if end_x < 0 || end_y < 0 || end_x >= self.height as isize || end_y >= self.width as isize {
+            return None;
+        }
+
+        let stop = (
+            (x + dx * (distance - 1)) as usize,
+            (y + dy * (distance - 1)) as usize,
+        );
+        Some(Guard::new(stop, guard.direction).turn())
+    }
+}
+
+fn ordered(length: usize, reversed: bool) -> Vec<usize> {
+    if reversed {
+        (0..length).rev().collect()
+    } else {
+        (0..length).collect()
+    }
+}
+
+/// One bit per (cell, direction) guard state.
+#[derive(Debug)]
+struct StateSet {
+    width: usize,
+    bits: Vec<u64>,
+}
+
+impl StateSet {
+    fn new(height: usize, width: usize) -> Self {
+        Self {
+            width,
+            bits: vec![0; (height * width * DIRECTIONS.len()).div_ceil(64)],
+        }
+    }
+
+    /// Returns `false` when the state was already in the set.
+    fn insert(&mut self, guard: Guard) -> bool {
+        let (x, y) = guard.position;
+        let index = (x * self.width + y) * DIRECTIONS.len() + guard.direction;
+        let (word, bit) = (index / 64, 1 << (index % 64));
+        let is_new = self.bits[word] & bit == 0;
+        self.bits[word] |= bit;
+
+        is_new
+    }
+
+    fn clear(&mut self) {
+        self.bits.fill(0);
+    }
+}
+
+/// The obstruction that traps the guard along with the states that make up the loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct LoopCertificate {
+    obstruction: (usize, usize),
+    cycle: Vec<Guard>,
+}
+
+impl LoopCertificate {
+    /// Replays the cycle with the obstruction in place and confirms it closes on itself.
+    #[cfg(test)]
+    fn is_valid(&self, grid: &[Vec<char>]) -> bool {
+        let Some(&first) = self.cycle.first() else {
+            return false;
+        };
+
+        let replay = Simulator::new(grid, first)
+            .with_obstruction(self.obstruction)
+            .take(self.cycle.len() + 1)
+            .collect::<Vec<Guard>>();
+
+        replay.len() == self.cycle.len() + 1
+            && replay[..self.cycle.len()] == self.cycle[..]
+            && replay[self.cycle.len()] == first
+    }
+}
+
+pub fn puzzle1(render: Option<&Path>) {
     let file_path = build_data_file_path(&Day::Day6, "data.txt").unwrap();
-    let lines = read_lines(file_path).unwrap();
+    let lines = read_lines(&file_path).unwrap();
     let result = calculate_part_one(lines).unwrap();
 
+    if let Some(dir) = render {
+        let grid = build_twod_vec(read_lines(&file_path).unwrap()).unwrap();
+        render_guard(&grid, dir, "day6_puzzle1", false).unwrap();
+    }
+
     println!("{result}");
 }
 
-pub fn puzzle2() {
+pub fn puzzle2(render: Option<&Path>) {
     let file_path = build_data_file_path(&Day::Day6, "data.txt").unwrap();
-    let lines = read_lines(file_path).unwrap();
+    let lines = read_lines(&file_path).unwrap();
     let result = calculate_part_two(lines).unwrap();
 
+    if let Some(dir) = render {
+        let grid = build_twod_vec(read_lines(&file_path).unwrap()).unwrap();
+        render_guard(&grid, dir, "day6_puzzle2", true).unwrap();
+    }
+
     println!("{result}");
 }
 
 fn calculate_part_one(lines: Lines<BufReader<File>>) -> Result<usize> {
-    let mut total = 0;
     let grid = build_twod_vec(lines).unwrap();
-    let turning_points = find_turning_points(&grid);
-
-    for x in 0..grid.len() {
-        for y in 0..grid[0].len() {
-            if grid[x][y] == '^' {
-                let visits = traverse_grid(&grid, (x, y), 3, &turning_points);
-                if visits > total {
-                    total = visits
-                }
-            }
-        }
-    }
+    let start = find_start(&grid)?;
+    let path = visited_path(&grid, start);
 
-    Ok(total)
+    Ok(path.len())
 }
 
 fn calculate_part_two(lines: Lines<BufReader<File>>) -> Result<usize> {
-    let total = AtomicUsize::new(0);
     let grid = build_twod_vec(lines).unwrap();
-    let turning_points = find_turning_points(&grid);
-    let start = grid
-        .iter()
+    let start = find_start(&grid)?;
+    Ok(find_loops(&grid, start).len())
+}
+
+fn find_start(grid: &[Vec<char>]) -> Result<Guard> {
+    grid.iter()
         .enumerate()
         .find_map(|(x, row)| {
             row.iter()
                 .enumerate()
                 .find(|&(_, &cell)| cell == '^')
-                .map(|(y, _)| (x, y))
-        })
-        .unwrap();
-    (0..grid.len()).into_par_iter().for_each(|x| {
-        (0..grid[0].len()).into_par_iter().for_each(|y| {
-            if grid[x][y] == '.' {
-                let mut points = turning_points.clone();
-                points.insert((x, y));
-                if traverse_grid_cycle(&grid, start, 3, &points) {
-                    total.fetch_add(1, Ordering::SeqCst);
-                }
-            }
+                .map(|(y, _)| Guard::new((x, y), 0))
         })
-    });
-
-    Ok(total.load(Ordering::SeqCst))
+        .ok_or_else(|| anyhow!("No guard found in the grid"))
 }
 
-fn traverse_grid(
-    grid: &[Vec<char>],
-    start: (usize, usize),
-    start_direction: usize,
-    turning_points: &HashSet<(usize, usize)>,
-) -> usize {
-    let directions = [(0, 1), (1, 0), (0, -1), (-1, 0)];
-    let mut direction_index = start_direction;
-    let mut position = start;
+/// Every distinct position the guard visits, in the order it first reaches them.
+fn visited_path(grid: &[Vec<char>], start: Guard) -> Vec<(usize, usize)> {
     let mut visited = vec![vec![false; grid[0].len()]; grid.len()];
-    let mut visit_count = 0;
-
-    loop {
-        let (x, y) = position;
+    let mut path = Vec::new();
 
+    for guard in Simulator::new(grid, start) {
+        let (x, y) = guard.position;
         if !visited[x][y] {
             visited[x][y] = true;
-            visit_count += 1;
-        }
-
-        let (dx, dy) = directions[direction_index];
-        let new_x = x as isize + dx;
-        let new_y = y as isize + dy;
-
-        if new_x < 0
-            || new_y < 0
-            || new_x >= grid.len() as isize
-            || new_y >= grid[0].len() as isize
-            || new_x == grid.len() as isize
-        {
-            break;
-        }
-
-        let new_x = new_x as usize;
-        let new_y = new_y as usize;
-
-        if turning_points.contains(&(new_x, new_y)) {
-            if direction_index == directions.len() - 1 {
-                position = (x, y);
-                direction_index = 0;
-                continue;
-            } else {
-                position = (x, y);
-                direction_index += 1;
-                continue;
-            }
+            path.push(guard.position);
         }
-
-        position = (new_x, new_y);
     }
 
-    visit_count
+    path
 }
 
-fn traverse_grid_cycle(
+/// Jumps from turn to turn until the guard leaves or repeats a turn. Once a turn repeats the
+/// guard is in the loop, which is then walked step by step to build the certificate.
+fn find_loop(
     grid: &[Vec<char>],
-    start: (usize, usize),
-    start_direction: usize,
-    turning_points: &HashSet<(usize, usize)>,
-) -> bool {
-    let directions = [(0, 1), (1, 0), (0, -1), (-1, 0)];
-    let mut direction_index = start_direction;
-    let mut position = start;
-    let mut cycle_check = HashSet::new();
+    table: &JumpTable,
+    seen: &mut StateSet,
+    start: Guard,
+    obstruction: (usize, usize),
+) -> Option<LoopCertificate> {
+    seen.clear();
+    let mut guard = start;
 
     loop {
-        let (x, y) = position;
-
-        if cycle_check.contains(&(position, direction_index)) {
-            return true;
-        }
-
-        cycle_check.insert((position, direction_index));
-
-        let (dx, dy) = directions[direction_index];
-        let new_x = x as isize + dx;
-        let new_y = y as isize + dy;
-
-        if new_x < 0
-            || new_y < 0
-            || new_x >= grid.len() as isize
-            || new_y >= grid[0].len() as isize
-            || new_x == grid.len() as isize
-        {
+        guard = table.jump(guard, Some(obstruction))?;
+        if !seen.insert(guard) {
             break;
         }
-
-        let new_x = new_x as usize;
-        let new_y = new_y as usize;
-
-        if turning_points.contains(&(new_x, new_y)) {
-            if direction_index == directions.len() - 1 {
-                position = (x, y);
-                direction_index = 0;
-                continue;
-            } else {
-                position = (x, y);
-                direction_index += 1;
-                continue;
-            }
-        }
-
-        position = (new_x, new_y);
     }
 
-    false
+    let mut cycle = vec![guard];
+    cycle.extend(
+        Simulator::new(grid, guard)
+            .with_obstruction(obstruction)
+            .skip(1)
+            .take_while(|&state| state != guard),
+    );
+
+    Some(LoopCertificate { obstruction, cycle })
 }
 
-fn find_turning_points(grid: &[Vec<char>]) -> HashSet<(usize, usize)> {
-    grid.iter()
-        .enumerate()
-        .flat_map(|(x, row)| {
-            row.iter()
-                .enumerate()
-                .filter(|&(_, &cell)| cell == '#')
-                .map(move |(y, _)| (x, y))
-        })
+/// An obstruction can only change the guard's route if it sits somewhere on the original path,
+/// so those are the only candidates checked.
+fn find_loops(grid: &[Vec<char>], start: Guard) -> Vec<LoopCertificate> {
+    let table = JumpTable::new(grid);
+
+    visited_path(grid, start)
+        .into_par_iter()
+        .filter(|&position| position != start.position)
+        .map_init(
+            || StateSet::new(grid.len(), grid[0].len()),
+            |seen, obstruction| find_loop(grid, &table, seen, start, obstruction),
+        )
+        .flatten()
         .collect()
 }
 
+/// Draws the guard's path and, when requested, every obstruction that would trap the guard.
+fn render_guard(grid: &[Vec<char>], dir: &Path, name: &str, with_loops: bool) -> Result<()> {
+    let start = find_start(grid)?;
+    let path = visited_path(grid, start);
+    let obstructions = if with_loops {
+        find_loops(grid, start)
+            .iter()
+            .map(|certificate| certificate.obstruction)
+            .collect::<Vec<(usize, usize)>>()
+    } else {
+        Vec::new()
+    };
+    let renderer = Renderer::new(grid)
+        .with_path(&path)
+        .with_highlights(&obstructions);
+    renderer.write_ascii(dir, name)?;
+    renderer.write_image(dir, name, ImageFormat::Ppm)?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::grid_from_str;
     use std::{
         fs::{create_dir_all, File},
         io::prelude::*,
@@ -251,4 +417,114 @@ mod tests {
 
         assert_eq!(result, 6);
     }
+
+    fn build_example_grid() -> Vec<Vec<char>> {
+        grid_from_str(
+            r#"....#.....
+.........#
+..........
+..#.......
+.......#..
+..........
+.#..^.....
+........#.
+#.........
+......#..."#,
+        )
+    }
+
+    #[test]
+    fn test_simulator_turns_in_place() {
+        let grid = build_example_grid();
+        let start = find_start(&grid).unwrap();
+        let steps = Simulator::new(&grid, start).take(7).collect::<Vec<Guard>>();
+
+        assert_eq!(steps[0], Guard::new((6, 4), 0));
+        assert_eq!(steps[5], Guard::new((1, 4), 0));
+        assert_eq!(steps[6], Guard::new((1, 4), 1));
+    }
+
+    #[test]
+    fn test_visited_path() {
+        let grid = build_example_grid();
+        let start = find_start(&grid).unwrap();
+        let path = visited_path(&grid, start);
+
+        assert_eq!(path.len(), 41);
+        assert_eq!(path[0], (6, 4));
+        assert_eq!(path[path.len() - 1], (9, 7));
+    }
+
+    #[test]
+    fn test_find_loops() {
+        let grid = build_example_grid();
+        let start = find_start(&grid).unwrap();
+        let mut obstructions = find_loops(&grid, start)
+            .iter()
+            .map(|certificate| certificate.obstruction)
+            .collect::<Vec<(usize, usize)>>();
+        obstructions.sort();
+
+        assert_eq!(
+            obstructions,
+            vec![(6, 3), (7, 6), (7, 7), (8, 1), (8, 3), (9, 7)]
+        );
+    }
+
+    #[test]
+    fn test_loop_certificate() {
+        let grid = build_example_grid();
+        let start = find_start(&grid).unwrap();
+        let table = JumpTable::new(&grid);
+        let mut seen = StateSet::new(grid.len(), grid[0].len());
+        let certificate = find_loop(&grid, &table, &mut seen, start, (6, 3)).unwrap();
+
+        assert!(certificate.is_valid(&grid));
+        assert!(find_loop(&grid, &table, &mut seen, start, (0, 0)).is_none());
+    }
+
+    #[test]
+    fn test_jump_table() {
+        let grid = build_example_grid();
+        let start = find_start(&grid).unwrap();
+        let table = JumpTable::new(&grid);
+
+        assert_eq!(table.jump(start, None), Some(Guard::new((1, 4), 1)));
+        assert_eq!(table.jump(start, Some((3, 4))), Some(Guard::new((4, 4), 1)));
+        assert_eq!(table.jump(start, Some((7, 4))), Some(Guard::new((1, 4), 1)));
+        assert_eq!(table.jump(Guard::new((9, 7), 2), None), None);
+        assert_eq!(
+            table.jump(Guard::new((0, 3), 1), None),
+            Some(Guard::new((0, 3), 2))
+        );
+    }
+
+    #[test]
+    fn test_jumps_match_simulator() {
+        let grid = build_example_grid();
+        let start = find_start(&grid).unwrap();
+        let table = JumpTable::new(&grid);
+        let turns = Simulator::new(&grid, start)
+            .collect::<Vec<Guard>>()
+            .windows(2)
+            .filter(|pair| pair[0].position == pair[1].position)
+            .map(|pair| pair[1])
+            .collect::<Vec<Guard>>();
+        let jumps =
+            std::iter::successors(table.jump(start, None), |&guard| table.jump(guard, None))
+                .collect::<Vec<Guard>>();
+
+        assert_eq!(jumps, turns);
+    }
+
+    #[test]
+    fn test_state_set() {
+        let mut seen = StateSet::new(2, 3);
+
+        assert!(seen.insert(Guard::new((1, 2), 3)));
+        assert!(!seen.insert(Guard::new((1, 2), 3)));
+        assert!(seen.insert(Guard::new((1, 2), 2)));
+        seen.clear();
+        assert!(seen.insert(Guard::new((1, 2), 3)));
+    }
 }
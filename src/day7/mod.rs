@@ -1,113 +1,234 @@
 use std::{
-    collections::VecDeque,
     fs::File,
     io::{BufReader, Lines},
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
 
 use crate::{
-    utils::{build_data_file_path, read_lines, split_whitespace_to_usize},
+    answer::{self, Answer},
+    utils::{
+        build_data_file_path,
+        digits::{concat, strip_suffix},
+        read_lines,
+    },
     Day,
 };
 
-pub fn puzzle1() {
-    let file_path = build_data_file_path(&Day::Day7, "data.txt").unwrap();
-    let lines = read_lines(file_path).unwrap();
-    let result = calculate_part_one(lines).unwrap();
+const PART_ONE_OPERATORS: &[Operator] = &[Operator::Add, Operator::Multiply];
+const PART_TWO_OPERATORS: &[Operator] = &[Operator::Add, Operator::Multiply, Operator::Concat];
 
-    println!("{result}");
+/// Operators are always evaluated left to right. Results that would overflow or go below zero
+/// make the equation invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Operator {
+    Add,
+    Multiply,
+    Concat,
+    Subtract,
+    Xor,
 }
 
-pub fn puzzle2() {
-    let file_path = build_data_file_path(&Day::Day7, "data.txt").unwrap();
-    let lines = read_lines(file_path).unwrap();
-    let result = calculate_part_two(lines).unwrap();
-
-    println!("{result}");
+/// What the left operand had to be for the operator to produce a result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Inverse {
+    None,
+    Value(usize),
+    /// Every left operand works, which only happens when multiplying by zero.
+    Any,
 }
 
-fn calculate_part_one(lines: Lines<BufReader<File>>) -> Result<usize> {
-    let mut total = 0;
-
-    for line in lines.map_while(Result::ok) {
-        let (answer_str, values_str) = line.split_once(": ").unwrap();
-        let answer = answer_str.parse::<usize>().unwrap();
-        let values = split_whitespace_to_usize(values_str);
+impl Operator {
+    fn symbol(&self) -> &str {
+        match self {
+            Operator::Add => "+",
+            Operator::Multiply => "*",
+            Operator::Concat => "||",
+            Operator::Subtract => "-",
+            Operator::Xor => "^",
+        }
+    }
 
-        if let Some(valid) = get_valid(&values, &answer) {
-            total += valid;
+    fn apply(&self, left: usize, right: usize) -> Option<usize> {
+        match self {
+            Operator::Add => left.checked_add(right),
+            Operator::Multiply => left.checked_mul(right),
+            Operator::Concat => concat(left, right),
+            Operator::Subtract => left.checked_sub(right),
+            Operator::Xor => Some(left ^ right),
         }
     }
 
-    Ok(total)
+    /// Works out the left operand from the result, which is what lets the solver run backwards
+    /// from the target and drop a branch as soon as the numbers don't divide or end the same.
+    fn invert(&self, result: usize, right: usize) -> Inverse {
+        let left = match self {
+            Operator::Add => result.checked_sub(right),
+            Operator::Multiply if right == 0 => {
+                return if result == 0 {
+                    Inverse::Any
+                } else {
+                    Inverse::None
+                };
+            }
+            Operator::Multiply => result.is_multiple_of(right).then(|| result / right),
+            Operator::Concat => strip_suffix(result, right),
+            Operator::Subtract => result.checked_add(right),
+            Operator::Xor => Some(result ^ right),
+        };
+
+        left.map_or(Inverse::None, Inverse::Value)
+    }
 }
 
-fn calculate_part_two(lines: Lines<BufReader<File>>) -> Result<usize> {
-    let mut total = 0;
+struct Solver<'a> {
+    operators: &'a [Operator],
+}
 
-    for line in lines.map_while(Result::ok) {
-        let (answer_str, values_str) = line.split_once(": ").unwrap();
-        let answer = answer_str.parse::<usize>().unwrap();
-        let values = split_whitespace_to_usize(values_str);
+impl<'a> Solver<'a> {
+    fn new(operators: &'a [Operator]) -> Self {
+        Self { operators }
+    }
 
-        if let Some(valid) = get_valid_with_concat(&values, &answer) {
-            total += valid;
+    /// The operator sequences that turn the values into the target. Stops at the first one
+    /// unless `all` is set.
+    fn solve(&self, target: usize, values: &[usize], all: bool) -> Vec<Vec<Operator>> {
+        let mut solutions = Vec::new();
+        if !values.is_empty() {
+            self.search(target, values, &mut Vec::new(), &mut solutions, all);
         }
+
+        solutions
     }
 
-    Ok(total)
-}
+    fn search(
+        &self,
+        target: usize,
+        values: &[usize],
+        suffix: &mut Vec<Operator>,
+        solutions: &mut Vec<Vec<Operator>>,
+        all: bool,
+    ) {
+        let Some((&last, rest)) = values.split_last() else {
+            return;
+        };
 
-fn get_valid(values: &[usize], answer: &usize) -> Option<usize> {
-    if &values.iter().copied().sum::<usize>() == answer {
-        return Some(*answer);
-    }
+        if rest.is_empty() {
+            if last == target {
+                solutions.push(suffix.iter().rev().copied().collect());
+            }
+            return;
+        }
 
-    let mut stack = VecDeque::new();
-    stack.push_back((values[0], 1));
+        for &operator in self.operators {
+            if !all && !solutions.is_empty() {
+                return;
+            }
 
-    while let Some((current_value, index)) = stack.pop_front() {
-        if index == values.len() {
-            if &current_value == answer {
-                return Some(*answer);
+            suffix.push(operator);
+            match operator.invert(target, last) {
+                Inverse::None => {}
+                Inverse::Value(left) => self.search(left, rest, suffix, solutions, all),
+                Inverse::Any => self.search_forward(rest[0], &rest[1..], suffix, solutions, all),
             }
-            continue;
+            suffix.pop();
         }
+    }
+
+    /// Tries every operator sequence over the values, keeping those that evaluate without
+    /// overflowing, followed by the operators already chosen for the rest of the equation.
+    fn search_forward(
+        &self,
+        current: usize,
+        values: &[usize],
+        suffix: &[Operator],
+        solutions: &mut Vec<Vec<Operator>>,
+        all: bool,
+    ) {
+        let mut prefixes = vec![(current, Vec::new())];
 
-        let next_num = values[index];
+        for &value in values {
+            prefixes = prefixes
+                .into_iter()
+                .flat_map(|(current, prefix)| {
+                    self.operators.iter().filter_map(move |operator| {
+                        let next = operator.apply(current, value)?;
+                        let mut prefix = prefix.clone();
+                        prefix.push(*operator);
+                        Some((next, prefix))
+                    })
+                })
+                .collect();
+        }
 
-        stack.push_back((current_value + next_num, index + 1));
-        stack.push_back((current_value * next_num, index + 1));
+        for (_, mut prefix) in prefixes {
+            if !all && !solutions.is_empty() {
+                return;
+            }
+            prefix.extend(suffix.iter().rev());
+            solutions.push(prefix);
+        }
     }
+}
+
+pub fn puzzle1(operators: Option<&[Operator]>, details: bool) {
+    let file_path = build_data_file_path(&Day::Day7, "data.txt").unwrap();
+    let lines = read_lines(file_path).unwrap();
+    let operators = operators.unwrap_or(PART_ONE_OPERATORS);
+    answer::report(calculate_total(lines, operators, details));
+}
 
-    None
+pub fn puzzle2(operators: Option<&[Operator]>, details: bool) {
+    let file_path = build_data_file_path(&Day::Day7, "data.txt").unwrap();
+    let lines = read_lines(file_path).unwrap();
+    let operators = operators.unwrap_or(PART_TWO_OPERATORS);
+    answer::report(calculate_total(lines, operators, details));
 }
 
-fn get_valid_with_concat(values: &[usize], answer: &usize) -> Option<usize> {
-    let mut stack = VecDeque::new();
-    stack.push_back((values[0], 1));
+/// Sums the targets that can be made. With `details` every solvable equation is printed with
+/// one of its expressions and how many operator sequences solve it.
+fn calculate_total(
+    lines: Lines<BufReader<File>>,
+    operators: &[Operator],
+    details: bool,
+) -> Result<Answer> {
+    let mut total = Answer::default();
+    let solver = Solver::new(operators);
+
+    for line in lines.map_while(Result::ok) {
+        let (answer_str, values_str) = line
+            .split_once(": ")
+            .ok_or_else(|| anyhow!("Invalid equation: {line}"))?;
+        let answer = answer_str.parse::<usize>()?;
+        let values = values_str
+            .split_whitespace()
+            .map(str::parse::<usize>)
+            .collect::<Result<Vec<usize>, _>>()?;
+        let solutions = solver.solve(answer, &values, details);
 
-    while let Some((current_value, index)) = stack.pop_front() {
-        if index == values.len() {
-            if &current_value == answer {
-                return Some(*answer);
+        if let Some(solution) = solutions.first() {
+            if details {
+                println!(
+                    "{answer} = {} ({} solutions)",
+                    expression(&values, solution),
+                    solutions.len()
+                );
             }
-            continue;
+            total = total.checked_add(&Answer::from(answer))?;
         }
+    }
 
-        let next_num = values[index];
-
-        stack.push_back((current_value + next_num, index + 1));
-        stack.push_back((current_value * next_num, index + 1));
+    Ok(total)
+}
 
-        let concatenated = format!("{}{}", current_value, next_num)
-            .parse::<usize>()
-            .unwrap();
-        stack.push_back((concatenated, index + 1));
+fn expression(values: &[usize], operators: &[Operator]) -> String {
+    let mut expression = values[0].to_string();
+    for (operator, value) in operators.iter().zip(&values[1..]) {
+        expression.push_str(&format!(" {} {value}", operator.symbol()));
     }
 
-    None
+    expression
 }
 
 #[cfg(test)]
@@ -136,9 +257,9 @@ mod tests {
 292: 11 6 16 20"#;
         file.write_all(data.as_bytes()).unwrap();
         let lines = read_lines(file_path).unwrap();
-        let result = calculate_part_one(lines).unwrap();
+        let result = calculate_total(lines, PART_ONE_OPERATORS, false).unwrap();
 
-        assert_eq!(result, 3749);
+        assert_eq!(result, Answer::from(3749_usize));
     }
 
     #[test]
@@ -158,8 +279,65 @@ mod tests {
 292: 11 6 16 20"#;
         file.write_all(data.as_bytes()).unwrap();
         let lines = read_lines(file_path).unwrap();
-        let result = calculate_part_two(lines).unwrap();
+        let result = calculate_total(lines, PART_TWO_OPERATORS, false).unwrap();
+
+        assert_eq!(result, Answer::from(11387_usize));
+    }
+
+    #[test]
+    fn test_solve_expressions() {
+        let solver = Solver::new(PART_ONE_OPERATORS);
+        let values = [81, 40, 27];
+        let solutions = solver.solve(3267, &values, true);
+        let expressions = solutions
+            .iter()
+            .map(|solution| expression(&values, solution))
+            .collect::<Vec<String>>();
 
-        assert_eq!(result, 11387);
+        assert_eq!(expressions, vec!["81 * 40 + 27", "81 + 40 * 27"]);
+        assert_eq!(solver.solve(3267, &values, false).len(), 1);
+    }
+
+    #[test]
+    fn test_solve_concat() {
+        let solver = Solver::new(PART_TWO_OPERATORS);
+        let values = [6, 8, 6, 15];
+        let solutions = solver.solve(7290, &values, true);
+
+        assert_eq!(
+            solutions,
+            vec![vec![
+                Operator::Multiply,
+                Operator::Concat,
+                Operator::Multiply
+            ]]
+        );
+        assert!(solver.solve(161011, &[16, 10, 13], false).is_empty());
+    }
+
+    #[test]
+    fn test_solve_subtract_and_xor() {
+        let solver = Solver::new(&[Operator::Subtract, Operator::Xor]);
+
+        assert_eq!(
+            solver.solve(3, &[10, 4, 5], true),
+            vec![vec![Operator::Subtract, Operator::Xor]]
+        );
+        assert!(solver.solve(7, &[2, 3], true).is_empty());
+    }
+
+    #[test]
+    fn test_solve_multiply_by_zero() {
+        let solver = Solver::new(PART_ONE_OPERATORS);
+
+        assert_eq!(solver.solve(0, &[3, 4, 0], true).len(), 2);
+        assert_eq!(solver.solve(5, &[3, 4, 0, 5], true).len(), 2);
+    }
+
+    #[test]
+    fn test_apply_overflow() {
+        assert_eq!(Operator::Concat.apply(usize::MAX, 1), None);
+        assert_eq!(Operator::Concat.apply(12, 345), Some(12345));
+        assert_eq!(Operator::Subtract.apply(1, 2), None);
     }
 }
@@ -1,5 +1,5 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashSet},
     fs::File,
     io::{BufReader, Lines},
 };
@@ -7,200 +7,175 @@ use std::{
 use anyhow::Result;
 
 use crate::{
-    utils::{build_data_file_path, build_twod_vec, read_lines},
+    utils::{build_data_file_path, build_twod_vec, math::gcd, read_lines},
     Day,
 };
 
-#[derive(Debug, Eq, PartialEq)]
-struct Position {
-    x: usize,
-    y: usize,
+/// Which points on the line through two antennas are antinodes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Harmonics {
+    /// Points where the distance to one antenna is one of these multiples of the distance to the
+    /// other.
+    Multiples(Vec<usize>),
+    /// Every point on the line.
+    All,
 }
 
-pub fn puzzle1() {
-    let file_path = build_data_file_path(&Day::Day8, "data.txt").unwrap();
-    let lines = read_lines(file_path).unwrap();
-    let result = calculate_part_one(lines).unwrap();
-
-    println!("{result}");
+struct AntinodeEngine<'a> {
+    grid: &'a [Vec<char>],
+    harmonics: &'a Harmonics,
 }
 
-pub fn puzzle2() {
-    let file_path = build_data_file_path(&Day::Day8, "data.txt").unwrap();
-    let lines = read_lines(file_path).unwrap();
-    let result = calculate_part_two(lines).unwrap();
+impl<'a> AntinodeEngine<'a> {
+    fn new(grid: &'a [Vec<char>], harmonics: &'a Harmonics) -> Self {
+        Self { grid, harmonics }
+    }
 
-    println!("{result}");
-}
+    fn antennas(&self) -> BTreeMap<char, Vec<(isize, isize)>> {
+        let mut antennas: BTreeMap<char, Vec<(isize, isize)>> = BTreeMap::new();
 
-fn calculate_part_one(lines: Lines<BufReader<File>>) -> Result<usize> {
-    let mut total = 0;
-    let grid = build_twod_vec(lines).unwrap();
-    let grid_len = grid.len() as isize;
-    let grid_width = grid[0].len() as isize;
-    let mut positions: HashMap<char, Vec<Position>> = HashMap::new();
-    let mut counted: HashSet<(isize, isize)> = HashSet::new();
-
-    for x in 0..grid.len() {
-        for y in 0..grid[0].len() {
-            if grid[x][y] == '.' {
-                continue;
+        for (x, row) in self.grid.iter().enumerate() {
+            for (y, &cell) in row.iter().enumerate() {
+                if cell != '.' {
+                    antennas
+                        .entry(cell)
+                        .or_default()
+                        .push((x as isize, y as isize));
+                }
             }
+        }
 
-            let current_position = Position { x, y };
-
-            if let Some(positions) = positions.get(&grid[x][y]) {
-                for position in positions {
-                    let offset_one = (
-                        2 * (position.x as isize - current_position.x as isize),
-                        2 * (position.y as isize - current_position.y as isize),
-                    );
-                    let offset_two = (
-                        2 * (current_position.x as isize - position.x as isize),
-                        2 * (current_position.y as isize - position.y as isize),
-                    );
-
-                    if (position.x as isize - offset_one.0) >= 0
-                        && (position.x as isize - offset_one.0) < grid_len
-                        && (position.y as isize - offset_one.1) >= 0
-                        && (position.y as isize - offset_one.1) < grid_width
-                        && !counted.contains(&(
-                            position.x as isize - offset_one.0,
-                            position.y as isize - offset_one.1,
-                        ))
-                    {
-                        total += 1;
-                        counted.insert((
-                            position.x as isize - offset_one.0,
-                            position.y as isize - offset_one.1,
-                        ));
-                    }
+        antennas
+    }
 
-                    if (current_position.x as isize - offset_two.0) >= 0
-                        && (current_position.x as isize - offset_two.0) < grid_len
-                        && (current_position.y as isize - offset_two.1) >= 0
-                        && (current_position.y as isize - offset_two.1) < grid_width
-                        && !counted.contains(&(
-                            current_position.x as isize - offset_two.0,
-                            current_position.y as isize - offset_two.1,
-                        ))
-                    {
-                        total += 1;
-                        counted.insert((
-                            current_position.x as isize - offset_two.0,
-                            current_position.y as isize - offset_two.1,
-                        ));
+    /// The antinodes made by each frequency's antennas.
+    fn antinodes(&self) -> BTreeMap<char, HashSet<(usize, usize)>> {
+        self.antennas()
+            .into_iter()
+            .map(|(frequency, positions)| {
+                let mut found = HashSet::new();
+                for (index, &first) in positions.iter().enumerate() {
+                    for &second in &positions[index + 1..] {
+                        found.extend(self.antinodes_between(first, second));
                     }
+                }
+                (frequency, found)
+            })
+            .collect()
+    }
 
-                    if (current_position.x as isize - offset_two.0) >= 0
-                        && (current_position.x as isize - offset_two.0) < grid_len
-                        && (current_position.y as isize - offset_two.1) >= 0
-                        && (current_position.y as isize - offset_two.1) < grid_width
-                        && !counted.contains(&(
-                            current_position.x as isize - offset_two.0,
-                            current_position.y as isize - offset_two.1,
-                        ))
-                    {
-                        total += 1;
-                        counted.insert((
-                            current_position.x as isize - offset_two.0,
-                            current_position.y as isize - offset_two.1,
-                        ));
+    /// Points on the line are `first + m * step`, where `step` is the difference between the
+    /// antennas divided by its gcd. That way lattice points between the antennas are reachable
+    /// too, and `second` sits at `m = gcd`.
+    fn antinodes_between(
+        &self,
+        first: (isize, isize),
+        second: (isize, isize),
+    ) -> Vec<(usize, usize)> {
+        let difference = (second.0 - first.0, second.1 - first.1);
+        let divisor = gcd(difference.0, difference.1);
+        let step = (difference.0 / divisor, difference.1 / divisor);
+        let point = |m: isize| (first.0 + m * step.0, first.1 + m * step.1);
+
+        let points = match self.harmonics {
+            Harmonics::Multiples(multiples) => multiples
+                .iter()
+                .flat_map(|&multiple| harmonic_offsets(multiple as isize, divisor))
+                .map(point)
+                .collect::<Vec<(isize, isize)>>(),
+            Harmonics::All => {
+                let mut points = Vec::new();
+                for direction in [1, -1] {
+                    let mut m = if direction == 1 { 0 } else { -1 };
+                    while self.is_in_bounds(point(m)) {
+                        points.push(point(m));
+                        m += direction;
                     }
                 }
+                points
             }
+        };
 
-            if let Some(positions) = positions.get_mut(&grid[x][y]) {
-                positions.push(Position { x, y });
-            } else {
-                positions.insert(grid[x][y], vec![Position { x, y }]);
-            }
-        }
+        points
+            .into_iter()
+            .filter(|&position| self.is_in_bounds(position))
+            .map(|(x, y)| (x as usize, y as usize))
+            .collect()
     }
 
-    Ok(total)
+    fn is_in_bounds(&self, (x, y): (isize, isize)) -> bool {
+        x >= 0 && y >= 0 && x < self.grid.len() as isize && y < self.grid[0].len() as isize
+    }
 }
 
-fn calculate_part_two(lines: Lines<BufReader<File>>) -> Result<usize> {
-    let mut total = 0;
-    let grid = build_twod_vec(lines).unwrap();
-    let grid_len = grid.len() as isize;
-    let grid_width = grid[0].len() as isize;
-    let mut positions: HashMap<char, Vec<Position>> = HashMap::new();
-    let mut counted: HashSet<(isize, isize)> = HashSet::new();
-
-    for x in 0..grid.len() {
-        for y in 0..grid[0].len() {
-            if grid[x][y] == '.' {
-                continue;
-            }
-
-            let current_position = Position { x, y };
-
-            if let Some(positions) = positions.get(&grid[x][y]) {
-                for position in positions {
-                    let offset_one = (
-                        position.x as isize - current_position.x as isize,
-                        position.y as isize - current_position.y as isize,
-                    );
-                    let offset_two = (
-                        current_position.x as isize - position.x as isize,
-                        current_position.y as isize - position.y as isize,
-                    );
-
-                    let mut check_x_position = position.x as isize - offset_one.0;
-                    let mut check_y_position = position.y as isize - offset_one.1;
-
-                    while check_x_position >= 0
-                        && check_x_position < grid_len
-                        && check_y_position >= 0
-                        && check_y_position < grid_width
-                    {
-                        if !counted.contains(&(check_x_position, check_y_position)) {
-                            total += 1;
-
-                            counted.insert((check_x_position, check_y_position));
-                        }
-
-                        check_x_position -= offset_one.0;
-                        check_y_position -= offset_one.1;
-                    }
+pub fn puzzle1(harmonics: Option<&Harmonics>, details: bool) {
+    let file_path = build_data_file_path(&Day::Day8, "data.txt").unwrap();
+    let lines = read_lines(file_path).unwrap();
+    let harmonics = harmonics.cloned().unwrap_or(Harmonics::Multiples(vec![2]));
+    let result = calculate_antinodes(lines, &harmonics, details).unwrap();
 
-                    let mut check_x_current_position = current_position.x as isize - offset_two.0;
-                    let mut check_y_current_position = current_position.y as isize - offset_two.1;
+    println!("{result}");
+}
 
-                    while check_x_current_position >= 0
-                        && check_x_current_position < grid_len
-                        && check_y_current_position >= 0
-                        && check_y_current_position < grid_width
-                    {
-                        if !counted.contains(&(check_x_current_position, check_y_current_position))
-                        {
-                            total += 1;
+pub fn puzzle2(harmonics: Option<&Harmonics>, details: bool) {
+    let file_path = build_data_file_path(&Day::Day8, "data.txt").unwrap();
+    let lines = read_lines(file_path).unwrap();
+    let harmonics = harmonics.cloned().unwrap_or(Harmonics::All);
+    let result = calculate_antinodes(lines, &harmonics, details).unwrap();
 
-                            counted.insert((check_x_current_position, check_y_current_position));
-                        }
+    println!("{result}");
+}
 
-                        check_x_current_position -= offset_two.0;
-                        check_y_current_position -= offset_two.1;
-                    }
-                }
-            }
+/// Counts the distinct antinode positions across all frequencies. With `details` the number of
+/// antinodes for each frequency is printed as well.
+fn calculate_antinodes(
+    lines: Lines<BufReader<File>>,
+    harmonics: &Harmonics,
+    details: bool,
+) -> Result<usize> {
+    let grid = build_twod_vec(lines).unwrap();
+    let antinodes = AntinodeEngine::new(&grid, harmonics).antinodes();
 
-            if let Some(positions) = positions.get_mut(&grid[x][y]) {
-                positions.push(Position { x, y });
-            } else {
-                positions.insert(grid[x][y], vec![Position { x, y }]);
-            }
+    if details {
+        for (frequency, positions) in &antinodes {
+            println!("{frequency}: {}", positions.len());
         }
     }
 
+    let total = antinodes
+        .values()
+        .flatten()
+        .collect::<HashSet<&(usize, usize)>>()
+        .len();
+
     Ok(total)
 }
 
+/// The offsets `m`, in steps from the first antenna, where the distance to one antenna is
+/// `multiple` times the distance to the other. The second antenna is `divisor` steps away, so
+/// this solves `|m| = multiple * |m - divisor|` and the mirrored equation, keeping whole steps.
+fn harmonic_offsets(multiple: isize, divisor: isize) -> Vec<isize> {
+    let mut offsets = Vec::new();
+    let mut push = |numerator: isize, denominator: isize| {
+        if denominator != 0 && numerator % denominator == 0 {
+            offsets.push(numerator / denominator);
+        }
+    };
+
+    push(multiple * divisor, multiple - 1);
+    push(multiple * divisor, multiple + 1);
+    push(-divisor, multiple - 1);
+    push(divisor, multiple + 1);
+
+    offsets.sort_unstable();
+    offsets.dedup();
+    offsets
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::grid_from_str;
     use std::{
         fs::{create_dir_all, File},
         io::prelude::*,
@@ -227,7 +202,7 @@ mod tests {
 ............"#;
         file.write_all(data.as_bytes()).unwrap();
         let lines = read_lines(file_path).unwrap();
-        let result = calculate_part_one(lines).unwrap();
+        let result = calculate_antinodes(lines, &Harmonics::Multiples(vec![2]), false).unwrap();
 
         assert_eq!(result, 14);
     }
@@ -252,8 +227,51 @@ mod tests {
 ............"#;
         file.write_all(data.as_bytes()).unwrap();
         let lines = read_lines(file_path).unwrap();
-        let result = calculate_part_two(lines).unwrap();
+        let result = calculate_antinodes(lines, &Harmonics::All, false).unwrap();
 
         assert_eq!(result, 34);
     }
+
+    #[test]
+    fn test_harmonic_offsets() {
+        assert_eq!(harmonic_offsets(2, 1), vec![-1, 2]);
+        assert_eq!(harmonic_offsets(2, 3), vec![-3, 1, 2, 6]);
+        assert_eq!(harmonic_offsets(1, 2), vec![1]);
+    }
+
+    #[test]
+    fn test_antinodes_per_frequency() {
+        let grid = grid_from_str(
+            "..........\n...a......\n..........\n....a.....\n..........\n.....A....\n..........",
+        );
+        let harmonics = Harmonics::Multiples(vec![2]);
+        let result = AntinodeEngine::new(&grid, &harmonics).antinodes();
+
+        assert!(result[&'A'].is_empty());
+        assert_eq!(result[&'a'], HashSet::from([(5, 5)]));
+    }
+
+    #[test]
+    fn test_all_harmonics_include_lattice_points() {
+        let grid = grid_from_str("a.....\n......\n......\n......\n....a.\n......");
+        let harmonics = Harmonics::All;
+        let result = AntinodeEngine::new(&grid, &harmonics).antinodes();
+
+        assert_eq!(
+            result[&'a'],
+            HashSet::from([(0, 0), (1, 1), (2, 2), (3, 3), (4, 4), (5, 5)])
+        );
+    }
+
+    #[test]
+    fn test_custom_harmonics() {
+        let grid = grid_from_str("a...a......");
+        let harmonics = Harmonics::Multiples(vec![1, 2, 3]);
+        let result = AntinodeEngine::new(&grid, &harmonics).antinodes();
+
+        assert_eq!(
+            result[&'a'],
+            HashSet::from([(0, 1), (0, 2), (0, 3), (0, 6), (0, 8)])
+        );
+    }
 }
@@ -1,146 +1,244 @@
-use std::fs::read_to_string;
-
-use anyhow::Result;
-
-use crate::{utils::build_data_file_path, Day};
-
-pub fn puzzle1() {
-    let file_path = build_data_file_path(&Day::Day9, "data.txt").unwrap();
-    let data = read_to_string(file_path).unwrap().trim().to_owned();
-    let result = calculate_part_one(&data).unwrap();
-
-    println!("{result}");
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fmt::{Display, Formatter},
+    fs::read_to_string,
+};
+
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+
+use crate::{
+    answer::{self, Answer},
+    utils::build_data_file_path,
+    Day,
+};
+
+/// How part two picks the free span a file moves into. Only spans left of the file are used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Strategy {
+    /// The leftmost span the file fits in.
+    First,
+    /// The smallest span the file fits in.
+    Best,
+    /// The largest span available.
+    Worst,
 }
 
-pub fn puzzle2() {
-    let file_path = build_data_file_path(&Day::Day9, "data.txt").unwrap();
-    let data = read_to_string(file_path).unwrap().trim().to_owned();
-    let result = calculate_part_two(&data).unwrap();
-
-    println!("{result}");
+/// A run of blocks belonging to one file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Span {
+    id: usize,
+    start: usize,
+    length: usize,
 }
 
-fn calculate_part_one(data: &str) -> Result<usize> {
-    let mut disk = build_disk(data).unwrap();
-
-    loop {
-        let last_block = disk.iter().rposition(|c| c != ".");
-        let first_empty = disk.iter().position(|c| c == ".");
-
-        if last_block.is_none()
-            || first_empty.is_none()
-            || last_block.unwrap() < first_empty.unwrap()
-        {
-            break;
-        }
+impl Span {
+    fn new(id: usize, start: usize, length: usize) -> Self {
+        Self { id, start, length }
+    }
 
-        disk.swap(last_block.unwrap(), first_empty.unwrap());
+    fn end(&self) -> usize {
+        self.start + self.length
     }
+}
 
-    let checksum = calculate_checksum(&disk).unwrap();
-    Ok(checksum)
+#[derive(Debug)]
+struct Disk {
+    files: Vec<Span>,
+    free: Vec<(usize, usize)>,
 }
 
-fn calculate_part_two(data: &str) -> Result<usize> {
-    let mut drive: Vec<Option<usize>> = Vec::new();
-    let mut curr_index: usize = 0;
-    let mut size: Vec<usize> = vec![0; data.chars().count()];
-    let mut loc: Vec<usize> = vec![0; data.chars().count()];
-
-    for (index, block) in data.chars().enumerate() {
-        if index % 2 == 0 {
-            loc[curr_index] = drive.len();
-            size[curr_index] = block.to_digit(10).unwrap() as usize;
-            for _ in 0..block.to_digit(10).unwrap() {
-                drive.push(Some(curr_index));
-            }
-            curr_index += 1;
-        } else {
-            for _ in 0..block.to_digit(10).unwrap() {
-                drive.push(None);
+impl Disk {
+    fn parse(disk_map: &str) -> Result<Self> {
+        let mut files = Vec::new();
+        let mut free = Vec::new();
+        let mut position = 0;
+
+        for (index, digit_char) in disk_map.trim().chars().enumerate() {
+            let length = digit_char
+                .to_digit(10)
+                .ok_or_else(|| anyhow!("Invalid disk map digit: {digit_char}"))?
+                as usize;
+
+            if index % 2 == 0 {
+                files.push(Span::new(index / 2, position, length));
+            } else if length > 0 {
+                free.push((position, length));
             }
+            position += length;
         }
-    }
 
-    let mut to_move = size.len() - 1;
-    while to_move > 0 {
-        let mut free_space: usize = 0;
-        let mut first_free: usize = 0;
+        Ok(Self { files, free })
+    }
 
-        while first_free < loc[to_move] && free_space < size[to_move] {
-            first_free += free_space;
-            free_space = 0;
-            while drive[first_free].is_some() {
-                first_free += 1
-            }
-            while first_free + free_space < drive.len() && drive[first_free + free_space].is_none()
-            {
-                free_space += 1
+    /// Moves single blocks from the end of the disk into the leftmost free blocks, splitting
+    /// files as needed. Walks the free spans from the left and the files from the right once.
+    fn compact_blocks(&self) -> Vec<Span> {
+        let mut layout = Vec::new();
+        let mut right = self.files.len();
+        let mut remaining = self.files.last().map_or(0, |file| file.length);
+
+        'free: for &(start, length) in &self.free {
+            let mut position = start;
+            let mut space = length;
+
+            while space > 0 && right > 0 {
+                let file = self.files[right - 1];
+                if file.start < position {
+                    break 'free;
+                }
+
+                let moved = space.min(remaining);
+                if moved > 0 {
+                    layout.push(Span::new(file.id, position, moved));
+                }
+                position += moved;
+                space -= moved;
+                remaining -= moved;
+
+                if remaining == 0 {
+                    right -= 1;
+                    remaining = right
+                        .checked_sub(1)
+                        .map_or(0, |index| self.files[index].length);
+                }
             }
         }
 
-        if first_free >= loc[to_move] {
-            to_move -= 1;
-            continue;
+        layout.extend(self.files[..right.saturating_sub(1)].iter().copied());
+        if let Some(index) = right.checked_sub(1) {
+            let file = self.files[index];
+            layout.push(Span::new(file.id, file.start, remaining));
         }
 
-        for block in drive.iter_mut().skip(first_free).take(size[to_move]) {
-            *block = Some(to_move as usize);
+        layout.retain(|span| span.length > 0);
+        layout.sort_by_key(|span| span.start);
+        layout
+    }
+
+    /// Moves whole files, highest id first, into free spans on their left. Free spans are kept in
+    /// one min-heap of start positions per span length, so finding a span only looks at the
+    /// front of each heap.
+    fn compact_files(&self, strategy: Strategy) -> Vec<Span> {
+        let max_length = self
+            .free
+            .iter()
+            .map(|&(_, length)| length)
+            .max()
+            .unwrap_or(0);
+        let mut free_by_length = vec![BinaryHeap::new(); max_length + 1];
+        for &(start, length) in &self.free {
+            free_by_length[length].push(Reverse(start));
         }
-        for block in drive.iter_mut().skip(loc[to_move]).take(size[to_move]) {
-            *block = None;
+
+        let mut layout = self.files.clone();
+        for file in layout.iter_mut().rev() {
+            let fits = (file.length.max(1)..=max_length).filter_map(|length| {
+                free_by_length[length]
+                    .peek()
+                    .filter(|Reverse(start)| *start < file.start)
+                    .map(|Reverse(start)| (length, *start))
+            });
+            let chosen = match strategy {
+                Strategy::First => fits.min_by_key(|&(_, start)| start),
+                Strategy::Best => fits.min_by_key(|&(length, _)| length),
+                Strategy::Worst => fits.max_by_key(|&(length, _)| length),
+            };
+
+            if let Some((length, start)) = chosen {
+                free_by_length[length].pop();
+                if length > file.length {
+                    free_by_length[length - file.length].push(Reverse(start + file.length));
+                }
+                file.start = start;
+            }
         }
-        to_move -= 1;
+
+        layout.sort_by_key(|span| span.start);
+        layout
     }
+}
+
+/// How broken up the disk is after compaction. Free space after the last file is not counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fragmentation {
+    free_spans: usize,
+    free_blocks: usize,
+    largest_free_span: usize,
+    split_files: usize,
+}
 
-    let mut result: usize = 0;
-    for (index, block) in drive.iter().enumerate() {
-        if block.is_some() {
-            result += index * block.unwrap();
-        } else {
-            continue;
+impl Fragmentation {
+    fn measure(layout: &[Span]) -> Self {
+        let gaps = layout
+            .windows(2)
+            .map(|pair| pair[1].start - pair[0].end())
+            .filter(|&gap| gap > 0)
+            .collect::<Vec<usize>>();
+        let mut ids = layout.iter().map(|span| span.id).collect::<Vec<usize>>();
+        ids.sort_unstable();
+        let split_files = ids
+            .chunk_by(|a, b| a == b)
+            .filter(|chunk| chunk.len() > 1)
+            .count();
+
+        Self {
+            free_spans: gaps.len(),
+            free_blocks: gaps.iter().sum(),
+            largest_free_span: gaps.iter().copied().max().unwrap_or(0),
+            split_files,
         }
     }
+}
 
-    Ok(result)
+impl Display for Fragmentation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} free blocks in {} spans, largest span {}, {} files split",
+            self.free_blocks, self.free_spans, self.largest_free_span, self.split_files
+        )
+    }
 }
 
-fn build_disk(disk_map: &str) -> Result<Vec<String>> {
-    let mut disk = Vec::new();
-    let mut current = 0;
+pub fn puzzle1(details: bool) {
+    let file_path = build_data_file_path(&Day::Day9, "data.txt").unwrap();
+    let data = read_to_string(file_path).unwrap().trim().to_owned();
+    answer::report(calculate_part_one(&data, details));
+}
+
+pub fn puzzle2(strategy: Strategy, details: bool) {
+    let file_path = build_data_file_path(&Day::Day9, "data.txt").unwrap();
+    let data = read_to_string(file_path).unwrap().trim().to_owned();
+    answer::report(calculate_part_two(&data, strategy, details));
+}
 
-    for (index, digit_char) in disk_map.chars().enumerate() {
-        let digit = digit_char.to_digit(10).unwrap();
-        let free_space = index % 2 != 0;
+fn calculate_part_one(data: &str, details: bool) -> Result<Answer> {
+    let layout = Disk::parse(data)?.compact_blocks();
+    if details {
+        println!("{}", Fragmentation::measure(&layout));
+    }
 
-        for _ in 0..digit {
-            if free_space {
-                disk.push(".".to_string());
-            } else {
-                disk.push(current.to_string());
-            }
-        }
+    calculate_checksum(&layout)
+}
 
-        if free_space {
-            current += 1;
-        }
+fn calculate_part_two(data: &str, strategy: Strategy, details: bool) -> Result<Answer> {
+    let layout = Disk::parse(data)?.compact_files(strategy);
+    if details {
+        println!("{}", Fragmentation::measure(&layout));
     }
 
-    Ok(disk)
+    calculate_checksum(&layout)
 }
 
-fn calculate_checksum(disk: &[String]) -> Result<usize> {
-    let mut checksum = 0;
-    for (index, block) in disk
-        .iter()
-        .filter(|&c| c.clone() != ".")
-        .map(|c| c.parse::<usize>().unwrap())
-        .collect::<Vec<usize>>()
-        .iter()
-        .enumerate()
-    {
-        let multiple = block * index;
-        checksum += multiple;
+/// Each span adds its id times the sum of its block positions.
+fn calculate_checksum(layout: &[Span]) -> Result<Answer> {
+    let mut checksum = Answer::default();
+    for span in layout.iter().filter(|span| span.length > 0) {
+        let (first, last) = (span.start as u128, span.end() as u128);
+        let positions = Answer::from((first + last - 1) * (last - first) / 2);
+        checksum = checksum.checked_add(&Answer::from(span.id).checked_mul(&positions)?)?;
     }
 
     Ok(checksum)
@@ -164,9 +262,9 @@ mod tests {
         let data = "2333133121414131402";
         file.write_all(data.as_bytes()).unwrap();
         let data = read_to_string(file_path).unwrap();
-        let result = calculate_part_one(&data).unwrap();
+        let result = calculate_part_one(&data, false).unwrap();
 
-        assert_eq!(result, 1928);
+        assert_eq!(result, Answer::from(1928_usize));
     }
 
     #[test]
@@ -178,75 +276,99 @@ mod tests {
         let data = "2333133121414131402";
         file.write_all(data.as_bytes()).unwrap();
         let data = read_to_string(file_path).unwrap();
-        let result = calculate_part_two(&data).unwrap();
+        let result = calculate_part_two(&data, Strategy::First, false).unwrap();
 
-        assert_eq!(result, 2858);
+        assert_eq!(result, Answer::from(2858_usize));
     }
 
     #[test]
-    fn test_build_disk() {
-        let map = "2333133121414131402";
-        let result = build_disk(map).unwrap();
-        let expected = vec![
-            "0".to_string(),
-            "0".to_string(),
-            ".".to_string(),
-            ".".to_string(),
-            ".".to_string(),
-            "1".to_string(),
-            "1".to_string(),
-            "1".to_string(),
-            ".".to_string(),
-            ".".to_string(),
-            ".".to_string(),
-            "2".to_string(),
-            ".".to_string(),
-            ".".to_string(),
-            ".".to_string(),
-            "3".to_string(),
-            "3".to_string(),
-            "3".to_string(),
-            ".".to_string(),
-            "4".to_string(),
-            "4".to_string(),
-            ".".to_string(),
-            "5".to_string(),
-            "5".to_string(),
-            "5".to_string(),
-            "5".to_string(),
-            ".".to_string(),
-            "6".to_string(),
-            "6".to_string(),
-            "6".to_string(),
-            "6".to_string(),
-            ".".to_string(),
-            "7".to_string(),
-            "7".to_string(),
-            "7".to_string(),
-            ".".to_string(),
-            "8".to_string(),
-            "8".to_string(),
-            "8".to_string(),
-            "8".to_string(),
-            "9".to_string(),
-            "9".to_string(),
-        ];
-
-        assert_eq!(result, expected);
+    fn test_zero_length_file() {
+        assert_eq!(
+            calculate_part_two("02", Strategy::First, false).unwrap(),
+            Answer::default()
+        );
+        assert_eq!(
+            calculate_part_two("0231", Strategy::First, false).unwrap(),
+            Answer::from(9_usize)
+        );
+        assert_eq!(calculate_part_one("02", false).unwrap(), Answer::default());
+    }
+
+    #[test]
+    fn test_parse_disk() {
+        let result = Disk::parse("12345").unwrap();
+
+        assert_eq!(
+            result.files,
+            vec![Span::new(0, 0, 1), Span::new(1, 3, 3), Span::new(2, 10, 5)]
+        );
+        assert_eq!(result.free, vec![(1, 2), (6, 4)]);
+    }
+
+    #[test]
+    fn test_compact_blocks() {
+        let result = Disk::parse("12345").unwrap().compact_blocks();
+
+        assert_eq!(
+            result,
+            vec![
+                Span::new(0, 0, 1),
+                Span::new(2, 1, 2),
+                Span::new(1, 3, 3),
+                Span::new(2, 6, 3),
+            ]
+        );
+        assert_eq!(calculate_checksum(&result).unwrap(), Answer::from(60_usize));
+    }
+
+    #[test]
+    fn test_compact_files_strategies() {
+        let disk = Disk::parse("1413121").unwrap();
+
+        assert_eq!(
+            disk.compact_files(Strategy::First),
+            vec![
+                Span::new(0, 0, 1),
+                Span::new(3, 1, 1),
+                Span::new(2, 2, 1),
+                Span::new(1, 3, 1),
+            ]
+        );
+        assert_eq!(
+            disk.compact_files(Strategy::Best),
+            vec![
+                Span::new(0, 0, 1),
+                Span::new(1, 1, 1),
+                Span::new(2, 6, 1),
+                Span::new(3, 10, 1),
+            ]
+        );
+        assert_eq!(
+            disk.compact_files(Strategy::Worst),
+            disk.compact_files(Strategy::First)
+        );
+    }
+
+    #[test]
+    fn test_fragmentation() {
+        let layout = vec![Span::new(0, 0, 2), Span::new(1, 4, 1), Span::new(0, 6, 1)];
+
+        assert_eq!(
+            Fragmentation::measure(&layout),
+            Fragmentation {
+                free_spans: 2,
+                free_blocks: 3,
+                largest_free_span: 2,
+                split_files: 1,
+            }
+        );
     }
 
     #[test]
     fn test_calculate_checksum() {
-        let data = vec![
-            "0".to_string(),
-            "0".to_string(),
-            "1".to_string(),
-            "1".to_string(),
-            "1".to_string(),
-            "2".to_string(),
-        ];
+        let data = vec![Span::new(0, 0, 2), Span::new(1, 2, 3), Span::new(2, 5, 1)];
         let result = calculate_checksum(&data).unwrap();
 
-        assert_eq!(result, 19);
+        assert_eq!(result, Answer::from(19_usize));
     }
 }
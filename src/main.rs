@@ -1,3 +1,4 @@
+mod answer;
 mod day1;
 mod day10;
 mod day11;
@@ -23,11 +24,15 @@ mod day6;
 mod day7;
 mod day8;
 mod day9;
+mod render;
 mod utils;
 
-use std::fmt::{Display, Formatter};
+use std::{
+    fmt::{Display, Formatter},
+    path::PathBuf,
+};
 
-use clap::{Parser, ValueEnum};
+use clap::{builder::RangedU64ValueParser, Parser, ValueEnum};
 
 #[derive(Clone, Debug, ValueEnum)]
 enum Day {
@@ -104,67 +109,188 @@ struct Args {
 
     /// The puzzle to run
     puzzle: Puzzle,
+
+    /// Directory to write renderings of the puzzle state to, for the days that support it
+    #[clap(long)]
+    render: Option<PathBuf>,
+
+    /// Print the given number of pairs that add the most distance on day1 puzzle one
+    #[clap(long)]
+    top: Option<usize>,
+
+    /// Print per item diagnostics for day2 reports, day4 word matches, day7 equations, day8
+    /// frequencies, day9 fragmentation, and day12 regions
+    #[clap(long)]
+    details: bool,
+
+    /// Smallest allowed difference between adjacent day2 levels
+    #[clap(long, default_value_t = 1)]
+    min_step: usize,
+
+    /// Largest allowed difference between adjacent day2 levels
+    #[clap(long, default_value_t = 3)]
+    max_step: usize,
+
+    /// Number of bad levels the day2 puzzle two dampener may remove
+    #[clap(long, default_value_t = 1)]
+    tolerance: usize,
+
+    /// Words to look for in the day4 puzzle one word search
+    #[clap(long, value_delimiter = ',', default_value = "XMAS")]
+    words: Vec<String>,
+
+    /// Only count day4 matches that do not share letters with an earlier match
+    #[clap(long)]
+    no_overlap: bool,
+
+    /// Let day4 words wrap around the edges of the grid
+    #[clap(long)]
+    wrap: bool,
+
+    /// Operators to try in the day7 equations instead of the puzzle's own set
+    #[clap(long, value_enum, value_delimiter = ',')]
+    operators: Option<Vec<day7::Operator>>,
+
+    /// Day8 antinode distance multiples to use instead of the puzzle's own rule
+    #[clap(long, value_delimiter = ',')]
+    harmonics: Option<Vec<usize>>,
+
+    /// How day9 puzzle two picks the free space a file moves into
+    #[clap(long, value_enum, default_value_t = day9::Strategy::First)]
+    strategy: day9::Strategy,
+
+    /// Number of times the day11 stones blink instead of the puzzle's own count
+    #[clap(long)]
+    blinks: Option<usize>,
+
+    /// Value a day11 stone engraved with 0 is replaced by
+    #[clap(long, default_value_t = 1)]
+    zero_becomes: usize,
+
+    /// Stop day11 stones with an even number of digits from splitting
+    #[clap(long)]
+    no_split: bool,
+
+    /// Value day11 stones are multiplied by when no other rule applies
+    #[clap(long, default_value_t = 2024)]
+    multiplier: usize,
+
+    /// Whether day12 plots also join a region through their corners
+    #[clap(long, value_enum, default_value_t = day12::Connectivity::Four)]
+    connectivity: day12::Connectivity,
+
+    /// How day12 fences are priced instead of the puzzle's own model
+    #[clap(long, value_enum)]
+    pricing: Option<day12::PricingModel>,
+
+    /// File of `<plant> <multiplier>` lines scaling the day12 price of each plant type
+    #[clap(long)]
+    price_table: Option<PathBuf>,
+
+    /// Write the day5 page ordering rules to this Graphviz DOT file
+    #[clap(long)]
+    dot: Option<PathBuf>,
+
+    /// Only export the pages of this day5 update, by its line number in the data file
+    #[clap(long)]
+    update: Option<usize>,
+
+    /// Shape to count in the day4 puzzle two grid, rows separated by `/` and `.` matching anything
+    #[clap(long, default_value = "M.S/.A./M.S")]
+    stencil: String,
+
+    /// Day14 only: width of the robot grid
+    #[clap(long, default_value_t = 101, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    width: usize,
+
+    /// Day14 only: height of the robot grid
+    #[clap(long, default_value_t = 103, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    height: usize,
+
+    /// Seconds to simulate for day14 puzzle one
+    #[clap(long, default_value_t = 100)]
+    seconds: usize,
+
+    /// Tokens it costs to press each day13 claw machine button, in the order they are listed
+    #[clap(long, value_delimiter = ',', default_value = "3,1")]
+    costs: Vec<i128>,
+
+    /// Most times a day13 button may be pressed instead of the puzzle's own limit. Required for
+    /// puzzle two when a machine has more than two buttons
+    #[clap(long)]
+    max_presses: Option<i128>,
 }
 
 fn main() {
     let args = Args::parse();
+    let render = args.render.as_deref();
+    let rules = day2::Rules::new(args.min_step, args.max_step, args.tolerance);
+    let search_options = day4::SearchOptions::new(!args.no_overlap, args.wrap);
+    let harmonics = args.harmonics.clone().map(day8::Harmonics::Multiples);
+    let stone_rules = day11::Rules::new(args.zero_becomes, !args.no_split, args.multiplier);
+    let costs = day13::Costs::new(args.costs.clone());
 
     match args.day {
         Day::Day1 => match args.puzzle {
-            Puzzle::Puzzle1 => day1::puzzle1(),
+            Puzzle::Puzzle1 => day1::puzzle1(args.top),
             Puzzle::Puzzle2 => day1::puzzle2(),
         },
         Day::Day2 => match args.puzzle {
-            Puzzle::Puzzle1 => day2::puzzle1(),
-            Puzzle::Puzzle2 => day2::puzzle2(),
+            Puzzle::Puzzle1 => day2::puzzle1(&rules, args.details),
+            Puzzle::Puzzle2 => day2::puzzle2(&rules, args.details),
         },
         Day::Day3 => match args.puzzle {
             Puzzle::Puzzle1 => day3::puzzle1(),
             Puzzle::Puzzle2 => day3::puzzle2(),
         },
         Day::Day4 => match args.puzzle {
-            Puzzle::Puzzle1 => day4::puzzle1(),
-            Puzzle::Puzzle2 => day4::puzzle2(),
+            Puzzle::Puzzle1 => day4::puzzle1(&args.words, search_options, args.details),
+            Puzzle::Puzzle2 => day4::puzzle2(&day4::Stencil::parse(&args.stencil).unwrap()),
         },
         Day::Day5 => match args.puzzle {
-            Puzzle::Puzzle1 => day5::puzzle1(),
-            Puzzle::Puzzle2 => day5::puzzle2(),
+            Puzzle::Puzzle1 => day5::puzzle1(args.dot.as_deref(), args.update),
+            Puzzle::Puzzle2 => day5::puzzle2(args.dot.as_deref(), args.update),
         },
         Day::Day6 => match args.puzzle {
-            Puzzle::Puzzle1 => day6::puzzle1(),
-            Puzzle::Puzzle2 => day6::puzzle2(),
+            Puzzle::Puzzle1 => day6::puzzle1(render),
+            Puzzle::Puzzle2 => day6::puzzle2(render),
         },
         Day::Day7 => match args.puzzle {
-            Puzzle::Puzzle1 => day7::puzzle1(),
-            Puzzle::Puzzle2 => day7::puzzle2(),
+            Puzzle::Puzzle1 => day7::puzzle1(args.operators.as_deref(), args.details),
+            Puzzle::Puzzle2 => day7::puzzle2(args.operators.as_deref(), args.details),
         },
         Day::Day8 => match args.puzzle {
-            Puzzle::Puzzle1 => day8::puzzle1(),
-            Puzzle::Puzzle2 => day8::puzzle2(),
+            Puzzle::Puzzle1 => day8::puzzle1(harmonics.as_ref(), args.details),
+            Puzzle::Puzzle2 => day8::puzzle2(harmonics.as_ref(), args.details),
         },
         Day::Day9 => match args.puzzle {
-            Puzzle::Puzzle1 => day9::puzzle1(),
-            Puzzle::Puzzle2 => day9::puzzle2(),
+            Puzzle::Puzzle1 => day9::puzzle1(args.details),
+            Puzzle::Puzzle2 => day9::puzzle2(args.strategy, args.details),
         },
         Day::Day10 => match args.puzzle {
             Puzzle::Puzzle1 => day10::puzzle1(),
             Puzzle::Puzzle2 => day10::puzzle2(),
         },
         Day::Day11 => match args.puzzle {
-            Puzzle::Puzzle1 => day11::puzzle1(),
-            Puzzle::Puzzle2 => day11::puzzle2(),
-        },
-        Day::Day12 => match args.puzzle {
-            Puzzle::Puzzle1 => day12::puzzle1(),
-            Puzzle::Puzzle2 => day12::puzzle2(),
+            Puzzle::Puzzle1 => day11::puzzle1(args.blinks, &stone_rules),
+            Puzzle::Puzzle2 => day11::puzzle2(args.blinks, &stone_rules),
         },
+        Day::Day12 => {
+            let garden_options =
+                day12::Options::new(args.connectivity, args.pricing, args.price_table.as_deref())
+                    .unwrap();
+            match args.puzzle {
+                Puzzle::Puzzle1 => day12::puzzle1(render, &garden_options, args.details),
+                Puzzle::Puzzle2 => day12::puzzle2(render, &garden_options, args.details),
+            }
+        }
         Day::Day13 => match args.puzzle {
-            Puzzle::Puzzle1 => day13::puzzle1(),
-            Puzzle::Puzzle2 => day13::puzzle2(),
+            Puzzle::Puzzle1 => day13::puzzle1(&costs, args.max_presses),
+            Puzzle::Puzzle2 => day13::puzzle2(&costs, args.max_presses),
         },
         Day::Day14 => match args.puzzle {
-            Puzzle::Puzzle1 => day14::puzzle1(),
-            Puzzle::Puzzle2 => day14::puzzle2(),
+            Puzzle::Puzzle1 => day14::puzzle1(render, args.width, args.height, args.seconds),
+            Puzzle::Puzzle2 => day14::puzzle2(render, args.width, args.height),
         },
         Day::Day15 => match args.puzzle {
             Puzzle::Puzzle1 => day15::puzzle1(),
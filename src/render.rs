@@ -0,0 +1,499 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write as _,
+    fs::{create_dir_all, write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+
+const CELL_SIZE: usize = 4;
+const SVG_CELL_SIZE: usize = 16;
+const PATH_CHAR: char = 'X';
+const HIGHLIGHT_CHAR: char = 'O';
+const EMPTY_COLOUR: [u8; 3] = [255, 255, 255];
+const WALL_COLOUR: [u8; 3] = [64, 64, 64];
+const CELL_COLOUR: [u8; 3] = [192, 192, 192];
+const PATH_COLOUR: [u8; 3] = [48, 96, 224];
+const HIGHLIGHT_COLOUR: [u8; 3] = [224, 32, 32];
+const FENCE_COLOUR: [u8; 3] = [0, 0, 0];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// Plain black and white bitmap, a cell is black when it holds anything other than `.`.
+    Pbm,
+    /// Binary RGB pixmap.
+    Ppm,
+}
+
+impl ImageFormat {
+    fn extension(&self) -> &str {
+        match self {
+            ImageFormat::Pbm => "pbm",
+            ImageFormat::Ppm => "ppm",
+        }
+    }
+}
+
+/// Draws a character grid with optional overlays. Highlights are drawn over the path and the
+/// path is drawn over region colours.
+#[derive(Debug)]
+pub struct Renderer<'a> {
+    grid: &'a [Vec<char>],
+    path: HashSet<(usize, usize)>,
+    highlights: HashSet<(usize, usize)>,
+    regions: Option<&'a [Vec<usize>]>,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(grid: &'a [Vec<char>]) -> Self {
+        Self {
+            grid,
+            path: HashSet::new(),
+            highlights: HashSet::new(),
+            regions: None,
+        }
+    }
+
+    pub fn with_path(mut self, path: &[(usize, usize)]) -> Self {
+        self.path.extend(path.iter().copied());
+        self
+    }
+
+    pub fn with_highlights(mut self, cells: &[(usize, usize)]) -> Self {
+        self.highlights.extend(cells.iter().copied());
+        self
+    }
+
+    /// `regions` holds a region id for every cell of the grid.
+    pub fn with_regions(mut self, regions: &'a [Vec<usize>]) -> Self {
+        self.regions = Some(regions);
+        self
+    }
+
+    pub fn to_ascii(&self) -> String {
+        let mut output = String::new();
+
+        for (x, row) in self.grid.iter().enumerate() {
+            for (y, &cell) in row.iter().enumerate() {
+                if self.highlights.contains(&(x, y)) {
+                    output.push(HIGHLIGHT_CHAR);
+                } else if self.path.contains(&(x, y)) {
+                    output.push(PATH_CHAR);
+                } else {
+                    output.push(cell);
+                }
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+
+    pub fn to_image(&self, format: ImageFormat) -> Vec<u8> {
+        let height = self.grid.len() * CELL_SIZE;
+        let width = self.grid.first().map_or(0, |row| row.len()) * CELL_SIZE;
+
+        match format {
+            ImageFormat::Pbm => {
+                let mut image = format!("P1\n{width} {height}\n").into_bytes();
+                for x in 0..height {
+                    let row = (0..width)
+                        .map(|y| {
+                            if self.is_set(x / CELL_SIZE, y / CELL_SIZE) {
+                                "1"
+                            } else {
+                                "0"
+                            }
+                        })
+                        .collect::<Vec<&str>>()
+                        .join(" ");
+                    image.extend(row.as_bytes());
+                    image.push(b'\n');
+                }
+                image
+            }
+            ImageFormat::Ppm => {
+                let mut image = format!("P6\n{width} {height}\n255\n").into_bytes();
+                for x in 0..height {
+                    for y in 0..width {
+                        image.extend(self.colour(x / CELL_SIZE, y / CELL_SIZE));
+                    }
+                }
+                image
+            }
+        }
+    }
+
+    pub fn write_ascii(&self, dir: &Path, name: &str) -> Result<PathBuf> {
+        create_dir_all(dir)?;
+        let file_path = dir.join(format!("{name}.txt"));
+        write(&file_path, self.to_ascii())?;
+
+        Ok(file_path)
+    }
+
+    pub fn write_image(&self, dir: &Path, name: &str, format: ImageFormat) -> Result<PathBuf> {
+        create_dir_all(dir)?;
+        let file_path = dir.join(format!("{name}.{}", format.extension()));
+        write(&file_path, self.to_image(format))?;
+
+        Ok(file_path)
+    }
+
+    /// Draws every region as a filled shape with its fence on top. Without regions each run of
+    /// the same character is treated as one.
+    pub fn to_svg(&self) -> String {
+        let height = self.grid.len();
+        let width = self.grid.first().map_or(0, |row| row.len());
+        let labels = match self.regions {
+            Some(regions) => regions.to_vec(),
+            None => self
+                .grid
+                .iter()
+                .map(|row| row.iter().map(|&cell| cell as usize).collect())
+                .collect(),
+        };
+        let mut output = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {width} {height}\">\n",
+            width * SVG_CELL_SIZE,
+            height * SVG_CELL_SIZE
+        );
+
+        for (label, outlines) in trace_outlines(&labels) {
+            let shape = outlines
+                .iter()
+                .map(Outline::to_path)
+                .collect::<Vec<String>>()
+                .join(" ");
+            writeln!(
+                output,
+                "  <path d=\"{shape}\" fill=\"{}\" fill-rule=\"evenodd\"/>",
+                svg_colour(region_colour(label))
+            )
+            .unwrap();
+
+            // Fences around holes are dashed so they stand out from the outer edge.
+            for outline in &outlines {
+                let dashes = if outline.is_hole() {
+                    " stroke-dasharray=\"0.25 0.1\""
+                } else {
+                    ""
+                };
+                writeln!(
+                    output,
+                    "  <path d=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"0.08\"{dashes}/>",
+                    outline.to_path(),
+                    svg_colour(FENCE_COLOUR)
+                )
+                .unwrap();
+            }
+        }
+        output.push_str("</svg>\n");
+
+        output
+    }
+
+    pub fn write_svg(&self, dir: &Path, name: &str) -> Result<PathBuf> {
+        create_dir_all(dir)?;
+        let file_path = dir.join(format!("{name}.svg"));
+        write(&file_path, self.to_svg())?;
+
+        Ok(file_path)
+    }
+
+    fn is_set(&self, x: usize, y: usize) -> bool {
+        self.grid[x][y] != '.' || self.path.contains(&(x, y)) || self.highlights.contains(&(x, y))
+    }
+
+    fn colour(&self, x: usize, y: usize) -> [u8; 3] {
+        if self.highlights.contains(&(x, y)) {
+            return HIGHLIGHT_COLOUR;
+        }
+
+        if self.path.contains(&(x, y)) {
+            return PATH_COLOUR;
+        }
+
+        if let Some(regions) = self.regions {
+            return region_colour(regions[x][y]);
+        }
+
+        match self.grid[x][y] {
+            '.' => EMPTY_COLOUR,
+            '#' => WALL_COLOUR,
+            _ => CELL_COLOUR,
+        }
+    }
+}
+
+/// A unit cell edge as its start point and direction.
+type Segment = ((usize, usize), (isize, isize));
+
+/// A closed fence line as its corner points in (x, y) lattice coordinates. Outer edges run
+/// clockwise on screen and the edges of holes run anticlockwise.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Outline {
+    points: Vec<(usize, usize)>,
+}
+
+impl Outline {
+    /// Twice the signed area, positive for clockwise outer edges and negative for holes.
+    fn winding(&self) -> isize {
+        let points = &self.points;
+        (0..points.len())
+            .map(|index| {
+                let (x1, y1) = points[index];
+                let (x2, y2) = points[(index + 1) % points.len()];
+                x1 as isize * y2 as isize - x2 as isize * y1 as isize
+            })
+            .sum()
+    }
+
+    fn is_hole(&self) -> bool {
+        self.winding() < 0
+    }
+
+    fn to_path(&self) -> String {
+        let points = self
+            .points
+            .iter()
+            .map(|(x, y)| format!("{x} {y}"))
+            .collect::<Vec<String>>();
+
+        format!("M {} Z", points.join(" L "))
+    }
+}
+
+/// Follows the fence around every region. Each cell edge facing another region becomes a
+/// directed segment with the region on its right, then segments are chained into closed loops.
+/// Where two cells of a region only touch at a corner the loop turns right, so it hugs each cell
+/// and every corner counted as a side shows up as a point.
+fn trace_outlines(labels: &[Vec<usize>]) -> BTreeMap<usize, Vec<Outline>> {
+    let label_at = |x: isize, y: isize| {
+        (y >= 0 && x >= 0)
+            .then(|| labels.get(y as usize)?.get(x as usize).copied())
+            .flatten()
+    };
+    let mut segments: BTreeMap<usize, Vec<Segment>> = BTreeMap::new();
+
+    for (y, row) in labels.iter().enumerate() {
+        for (x, &label) in row.iter().enumerate() {
+            let (cx, cy) = (x as isize, y as isize);
+            let edges = [
+                ((0, -1), (x, y), (1, 0)),
+                ((1, 0), (x + 1, y), (0, 1)),
+                ((0, 1), (x + 1, y + 1), (-1, 0)),
+                ((-1, 0), (x, y + 1), (0, -1)),
+            ];
+            for ((dx, dy), start, direction) in edges {
+                if label_at(cx + dx, cy + dy) != Some(label) {
+                    segments.entry(label).or_default().push((start, direction));
+                }
+            }
+        }
+    }
+
+    segments
+        .into_iter()
+        .map(|(label, edges)| (label, chain_segments(&edges)))
+        .collect()
+}
+
+fn chain_segments(edges: &[Segment]) -> Vec<Outline> {
+    let mut outgoing: HashMap<(usize, usize), Vec<(isize, isize)>> = HashMap::new();
+    for &(start, direction) in edges {
+        outgoing.entry(start).or_default().push(direction);
+    }
+
+    let mut take = |position: (usize, usize), direction: (isize, isize)| {
+        let directions = outgoing.get_mut(&position)?;
+        let index = directions.iter().position(|&d| d == direction)?;
+        directions.swap_remove(index);
+        Some(direction)
+    };
+    let mut outlines = Vec::new();
+
+    for &(start, first_direction) in edges {
+        if take(start, first_direction).is_none() {
+            continue;
+        }
+
+        let mut points = vec![start];
+        let mut direction = first_direction;
+        let mut position = step(start, direction);
+        loop {
+            let (dx, dy) = direction;
+            let next = [(-dy, dx), (dx, dy), (dy, -dx)]
+                .into_iter()
+                .find_map(|candidate| take(position, candidate));
+            let Some(next) = next else {
+                break;
+            };
+            if next != direction {
+                points.push(position);
+            }
+            direction = next;
+            position = step(position, direction);
+        }
+
+        if direction == first_direction {
+            points.remove(0);
+        }
+        outlines.push(Outline { points });
+    }
+
+    outlines
+}
+
+fn step((x, y): (usize, usize), (dx, dy): (isize, isize)) -> (usize, usize) {
+    ((x as isize + dx) as usize, (y as isize + dy) as usize)
+}
+
+fn svg_colour([red, green, blue]: [u8; 3]) -> String {
+    format!("#{red:02x}{green:02x}{blue:02x}")
+}
+
+/// Spreads region ids around the colour wheel so neighbouring ids get clearly different hues.
+fn region_colour(region: usize) -> [u8; 3] {
+    let hue = (region * 137) % 360;
+    let sector = hue / 60;
+    let rising = (hue % 60 * 255 / 60) as u8;
+    let falling = 255 - rising;
+    let low = 64;
+
+    match sector {
+        0 => [255, rising.max(low), low],
+        1 => [falling.max(low), 255, low],
+        2 => [low, 255, rising.max(low)],
+        3 => [low, falling.max(low), 255],
+        4 => [rising.max(low), low, 255],
+        _ => [255, low, falling.max(low)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::read_to_string;
+    use tempfile::tempdir;
+
+    fn build_grid() -> Vec<Vec<char>> {
+        vec![vec!['.', '#', '.'], vec!['.', '.', '.']]
+    }
+
+    #[test]
+    fn test_to_ascii() {
+        let grid = build_grid();
+        let renderer = Renderer::new(&grid)
+            .with_path(&[(1, 0), (1, 1), (1, 2)])
+            .with_highlights(&[(1, 2)]);
+
+        assert_eq!(renderer.to_ascii(), ".#.\nXXO\n");
+    }
+
+    #[test]
+    fn test_to_image_ppm() {
+        let grid = build_grid();
+        let image = Renderer::new(&grid).to_image(ImageFormat::Ppm);
+        let header = format!("P6\n{} {}\n255\n", 3 * CELL_SIZE, 2 * CELL_SIZE);
+
+        assert!(image.starts_with(header.as_bytes()));
+        assert_eq!(
+            image.len(),
+            header.len() + 3 * CELL_SIZE * 2 * CELL_SIZE * 3
+        );
+        assert_eq!(image[header.len()..header.len() + 3], EMPTY_COLOUR);
+        let wall = header.len() + CELL_SIZE * 3;
+        assert_eq!(image[wall..wall + 3], WALL_COLOUR);
+    }
+
+    #[test]
+    fn test_to_image_pbm() {
+        let grid = build_grid();
+        let image = Renderer::new(&grid).to_image(ImageFormat::Pbm);
+        let text = String::from_utf8(image).unwrap();
+        let mut rows = text.lines().skip(2);
+        let expected = [
+            vec!["0"; CELL_SIZE],
+            vec!["1"; CELL_SIZE],
+            vec!["0"; CELL_SIZE],
+        ]
+        .concat()
+        .join(" ");
+
+        assert!(text.starts_with(&format!("P1\n{} {}\n", 3 * CELL_SIZE, 2 * CELL_SIZE)));
+        assert_eq!(rows.next().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_region_colours_differ() {
+        let grid = build_grid();
+        let regions = vec![vec![0, 1, 0], vec![0, 0, 0]];
+        let renderer = Renderer::new(&grid).with_regions(&regions);
+
+        assert_ne!(renderer.colour(0, 0), renderer.colour(0, 1));
+        assert_eq!(renderer.colour(0, 0), renderer.colour(1, 2));
+    }
+
+    #[test]
+    fn test_write_ascii() {
+        let base = tempdir().unwrap().path().to_path_buf();
+        let grid = build_grid();
+        let file_path = Renderer::new(&grid).write_ascii(&base, "grid").unwrap();
+
+        assert_eq!(file_path, base.join("grid.txt"));
+        assert_eq!(read_to_string(file_path).unwrap(), ".#.\n...\n");
+    }
+
+    #[test]
+    fn test_trace_square_with_hole() {
+        let regions = vec![vec![0, 0, 0], vec![0, 1, 0], vec![0, 0, 0]];
+        let outlines = trace_outlines(&regions);
+
+        assert_eq!(outlines[&0][0].points, vec![(0, 0), (3, 0), (3, 3), (0, 3)]);
+        assert!(!outlines[&0][0].is_hole());
+        assert_eq!(outlines[&0][1].points, vec![(2, 1), (1, 1), (1, 2), (2, 2)]);
+        assert!(outlines[&0][1].is_hole());
+        assert_eq!(outlines[&1].len(), 1);
+    }
+
+    #[test]
+    fn test_svg_dashes_hole_fences() {
+        let grid = vec![vec!['A'; 3], vec!['A', 'B', 'A'], vec!['A'; 3]];
+        let svg = Renderer::new(&grid).to_svg();
+
+        assert_eq!(svg.matches("stroke-dasharray").count(), 1);
+        assert!(svg.contains("d=\"M 1 1 L 2 1 L 2 2 L 1 2 Z\" fill=\"none\""));
+    }
+
+    #[test]
+    fn test_trace_corner_touch_keeps_corners() {
+        let regions = vec![vec![0, 1], vec![1, 0]];
+        let outlines = trace_outlines(&regions);
+        let corners = outlines[&0]
+            .iter()
+            .map(|outline| outline.points.len())
+            .sum::<usize>();
+
+        assert_eq!(outlines[&0].len(), 2);
+        assert_eq!(corners, 8);
+    }
+
+    #[test]
+    fn test_write_svg() {
+        let base = tempdir().unwrap().path().to_path_buf();
+        let grid = build_grid();
+        let regions = vec![vec![0, 1, 0], vec![0, 0, 0]];
+        let file_path = Renderer::new(&grid)
+            .with_regions(&regions)
+            .write_svg(&base, "grid")
+            .unwrap();
+        let svg = read_to_string(file_path).unwrap();
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert_eq!(svg.matches("<path").count(), 4);
+        assert!(svg.contains("d=\"M 1 0 L 2 0 L 2 1 L 1 1 Z\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+}
@@ -0,0 +1,224 @@
+use std::{collections::HashMap, hash::Hash};
+
+/// How a sequence of states repeats: the first `tail` states are never seen again and every
+/// state after them comes back `length` steps later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    pub tail: usize,
+    pub length: usize,
+}
+
+impl Cycle {
+    /// The earliest step that has the same state as `step`.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn reduce(&self, step: usize) -> usize {
+        if step < self.tail {
+            step
+        } else {
+            self.tail + (step - self.tail) % self.length
+        }
+    }
+}
+
+/// Where the hare stopped in the first phase of Brent's algorithm, and the cycle length if it
+/// caught the tortoise.
+struct Chase<T> {
+    hare: T,
+    walked: usize,
+    length: Option<usize>,
+}
+
+/// Moves the tortoise up to the hare at every power of two until the hare lands on it, which
+/// only happens once both are in the cycle, or until the hare has taken `limit` steps.
+fn chase<T, F>(start: &T, step: &mut F, limit: usize) -> Option<Chase<T>>
+where
+    T: Clone + Eq,
+    F: FnMut(&T) -> Option<T>,
+{
+    let mut power = 1;
+    let mut length = 1;
+    let mut walked = 1;
+    let mut tortoise = start.clone();
+    let mut hare = step(start)?;
+    while tortoise != hare {
+        if walked >= limit {
+            return Some(Chase {
+                hare,
+                walked,
+                length: None,
+            });
+        }
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = step(&hare)?;
+        length += 1;
+        walked += 1;
+    }
+
+    Some(Chase {
+        hare,
+        walked,
+        length: Some(length),
+    })
+}
+
+/// Brent's algorithm, which finds the cycle in constant memory by comparing states instead of
+/// storing them. `step` returns `None` when the sequence ends, in which case there is no cycle.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn brent<T, F>(start: T, mut step: F) -> Option<Cycle>
+where
+    T: Clone + Eq,
+    F: FnMut(&T) -> Option<T>,
+{
+    let length = chase(&start, &mut step, usize::MAX)?.length?;
+
+    // With the hare a cycle length ahead, the two meet where the cycle starts.
+    let mut tortoise = start.clone();
+    let mut hare = start;
+    for _ in 0..length {
+        hare = step(&hare)?;
+    }
+    let mut tail = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise)?;
+        hare = step(&hare)?;
+        tail += 1;
+    }
+
+    Some(Cycle { tail, length })
+}
+
+/// Finds the cycle by remembering the step each state was first seen at. Uses memory for every
+/// state before the first repeat, but only walks the sequence once.
+pub fn find_cycle<T, F>(start: T, mut step: F) -> Option<Cycle>
+where
+    T: Clone + Eq + Hash,
+    F: FnMut(&T) -> Option<T>,
+{
+    let mut seen = HashMap::new();
+    let mut state = start;
+
+    loop {
+        let index = seen.len();
+        if let Some(&first) = seen.get(&state) {
+            return Some(Cycle {
+                tail: first,
+                length: index - first,
+            });
+        }
+
+        let next = step(&state)?;
+        seen.insert(state, index);
+        state = next;
+    }
+}
+
+/// The state after `steps` steps. The walk never takes more than `steps` steps, and once a cycle
+/// turns up only the part of a lap that is left is walked. Returns `None` if the sequence ends
+/// first.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn fast_forward<T, F>(start: T, mut step: F, steps: usize) -> Option<T>
+where
+    T: Clone + Eq,
+    F: FnMut(&T) -> Option<T>,
+{
+    if steps == 0 {
+        return Some(start);
+    }
+
+    let chase = chase(&start, &mut step, steps)?;
+    let remaining = chase
+        .length
+        .map_or(0, |length| (steps - chase.walked) % length);
+
+    (0..remaining).try_fold(chase.hare, |state, _| step(&state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 0 -> 1 -> 2 -> 3 -> 4 -> 2 -> ..., a tail of 2 then a cycle of 3.
+    fn rho(value: &usize) -> Option<usize> {
+        Some(if *value == 4 { 2 } else { value + 1 })
+    }
+
+    #[test]
+    fn test_brent() {
+        assert_eq!(brent(0, rho), Some(Cycle { tail: 2, length: 3 }));
+        assert_eq!(brent(3, rho), Some(Cycle { tail: 0, length: 3 }));
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        assert_eq!(find_cycle(0, rho), Some(Cycle { tail: 2, length: 3 }));
+        assert_eq!(find_cycle(3, rho), Some(Cycle { tail: 0, length: 3 }));
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        let step = |value: &u8| Some(*value);
+
+        assert_eq!(brent(7, step), Some(Cycle { tail: 0, length: 1 }));
+        assert_eq!(find_cycle(7, step), Some(Cycle { tail: 0, length: 1 }));
+    }
+
+    #[test]
+    fn test_detectors_agree() {
+        let step = |value: &u64| Some((value * value + 1) % 2027);
+
+        for start in [0, 1, 2, 100, 2026] {
+            assert_eq!(brent(start, step), find_cycle(start, step));
+        }
+    }
+
+    #[test]
+    fn test_sequence_that_ends() {
+        let step = |value: &usize| (*value < 10).then(|| value + 1);
+
+        assert_eq!(brent(0, step), None);
+        assert_eq!(find_cycle(0, step), None);
+        assert_eq!(fast_forward(0, step, 4), Some(4));
+        assert_eq!(fast_forward(0, step, 11), None);
+    }
+
+    #[test]
+    fn test_reduce() {
+        let cycle = Cycle { tail: 2, length: 3 };
+
+        assert_eq!(cycle.reduce(1), 1);
+        assert_eq!(cycle.reduce(2), 2);
+        assert_eq!(cycle.reduce(5), 2);
+        assert_eq!(cycle.reduce(1_000_000_000_001), 2);
+    }
+
+    #[test]
+    fn test_fast_forward() {
+        assert_eq!(fast_forward(0, rho, 0), Some(0));
+        assert_eq!(fast_forward(0, rho, 6), Some(3));
+        assert_eq!(fast_forward(0, rho, 1_000_000_000_000), Some(4));
+
+        let mut calls = 0;
+        let counted = |value: &usize| {
+            calls += 1;
+            rho(value)
+        };
+        fast_forward(0, counted, usize::MAX);
+        assert!(calls < 100);
+    }
+
+    #[test]
+    fn test_fast_forward_short_of_a_long_cycle() {
+        let mut calls = 0;
+        let counted = |value: &usize| {
+            calls += 1;
+            Some((value + 1) % 1_000_000)
+        };
+
+        assert_eq!(fast_forward(0, counted, 3), Some(3));
+        assert_eq!(calls, 3);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::utils::math::checked_pow10;
+
+/// The decimal digits of a number from least to most significant. Zero has the single digit 0.
+#[derive(Debug, Clone)]
+pub struct Digits {
+    value: usize,
+    done: bool,
+}
+
+impl Iterator for Digits {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let digit = (self.value % 10) as u8;
+        self.value /= 10;
+        self.done = self.value == 0;
+
+        Some(digit)
+    }
+}
+
+pub fn digits(value: usize) -> Digits {
+    Digits { value, done: false }
+}
+
+pub fn digit_count(value: usize) -> u32 {
+    digits(value).count() as u32
+}
+
+/// Splits a number with an even number of digits into its two halves, so 1000 becomes (10, 0).
+pub fn split_half(value: usize) -> Option<(usize, usize)> {
+    let count = digit_count(value);
+    if !count.is_multiple_of(2) {
+        return None;
+    }
+
+    let power = checked_pow10::<usize>(count / 2)?;
+    Some((value / power, value % power))
+}
+
+/// Writes the digits of `right` after those of `left`, or `None` if the result overflows.
+pub fn concat(left: usize, right: usize) -> Option<usize> {
+    left.checked_mul(checked_pow10(digit_count(right))?)?
+        .checked_add(right)
+}
+
+/// The number left after removing `suffix` from the end of `value`, if `value` ends with it.
+/// This undoes `concat`, so a value equal to the suffix leaves 0.
+pub fn strip_suffix(value: usize, suffix: usize) -> Option<usize> {
+    let remainder = value.checked_sub(suffix)?;
+
+    match checked_pow10::<usize>(digit_count(suffix)) {
+        Some(power) => remainder.is_multiple_of(power).then(|| remainder / power),
+        None => (remainder == 0).then_some(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digits() {
+        assert_eq!(digits(0).collect::<Vec<u8>>(), vec![0]);
+        assert_eq!(digits(1203).collect::<Vec<u8>>(), vec![3, 0, 2, 1]);
+    }
+
+    #[test]
+    fn test_digit_count() {
+        assert_eq!(digit_count(0), 1);
+        assert_eq!(digit_count(9), 1);
+        assert_eq!(digit_count(10), 2);
+        assert_eq!(digit_count(253000), 6);
+        assert_eq!(digit_count(usize::MAX), usize::MAX.to_string().len() as u32);
+    }
+
+    #[test]
+    fn test_split_half() {
+        assert_eq!(split_half(1000), Some((10, 0)));
+        assert_eq!(split_half(253000), Some((253, 0)));
+        assert_eq!(split_half(17), Some((1, 7)));
+        assert_eq!(split_half(125), None);
+        assert_eq!(split_half(0), None);
+    }
+
+    #[test]
+    fn test_concat() {
+        assert_eq!(concat(12, 345), Some(12345));
+        assert_eq!(concat(15, 6), Some(156));
+        assert_eq!(concat(1, 0), Some(10));
+        assert_eq!(concat(0, 7), Some(7));
+        assert_eq!(concat(usize::MAX, 1), None);
+        assert_eq!(concat(1, usize::MAX), None);
+    }
+
+    #[test]
+    fn test_strip_suffix() {
+        assert_eq!(strip_suffix(12345, 345), Some(12));
+        assert_eq!(strip_suffix(156, 6), Some(15));
+        assert_eq!(strip_suffix(10, 0), Some(1));
+        assert_eq!(strip_suffix(7, 7), Some(0));
+        assert_eq!(strip_suffix(12345, 45), Some(123));
+        assert_eq!(strip_suffix(12345, 44), None);
+        assert_eq!(strip_suffix(5, 15), None);
+        assert_eq!(strip_suffix(usize::MAX, usize::MAX), Some(0));
+    }
+
+    #[test]
+    fn test_concat_round_trip() {
+        for (left, right) in [(1, 2), (48, 1000), (0, 0), (999, 1)] {
+            assert_eq!(
+                strip_suffix(concat(left, right).unwrap(), right),
+                Some(left)
+            );
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use std::ops::{Div, Rem, Sub};
+
+/// The primitive integer types the helpers below work over.
+pub trait Integer:
+    Copy + Ord + Sub<Output = Self> + Div<Output = Self> + Rem<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const TEN: Self;
+
+    fn checked_mul(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! impl_integer {
+    ($($int:ty),*) => {
+        $(
+            impl Integer for $int {
+                const ZERO: Self = 0;
+                const ONE: Self = 1;
+                const TEN: Self = 10;
+
+                fn checked_mul(self, other: Self) -> Option<Self> {
+                    <$int>::checked_mul(self, other)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(i32, i64, i128, isize, u32, u64, u128, usize);
+
+fn abs<T: Integer>(value: T) -> T {
+    if value < T::ZERO {
+        T::ZERO - value
+    } else {
+        value
+    }
+}
+
+/// The non-negative greatest common divisor, with `gcd(0, 0) == 0`.
+pub fn gcd<T: Integer>(a: T, b: T) -> T {
+    let (mut a, mut b) = (a, b);
+    while b != T::ZERO {
+        (a, b) = (b, a % b);
+    }
+
+    abs(a)
+}
+
+/// The non-negative least common multiple, or `None` if it does not fit in `T`.
+pub fn lcm<T: Integer>(a: T, b: T) -> Option<T> {
+    if a == T::ZERO || b == T::ZERO {
+        return Some(T::ZERO);
+    }
+
+    abs(a / gcd(a, b)).checked_mul(abs(b))
+}
+
+/// Returns `(g, x, y)` where `g` is the non-negative gcd of `a` and `b` and `a * x + b * y = g`.
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_x, x) = (x, old_x - quotient * x);
+        (old_y, y) = (y, old_y - quotient * y);
+    }
+
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// The `x` in `0..modulus` with `a * x ≡ 1 (mod modulus)`, if `a` and `modulus` are coprime.
+pub fn mod_inverse(a: i128, modulus: i128) -> Option<i128> {
+    if modulus <= 0 {
+        return None;
+    }
+
+    let (gcd, x, _) = extended_gcd(a.rem_euclid(modulus), modulus);
+    (gcd == 1).then(|| x.rem_euclid(modulus))
+}
+
+/// Solves `x ≡ residue (mod modulus)` for every pair at once, returning the smallest
+/// non-negative `x` and the modulus it repeats with. The moduli do not need to be coprime; `None`
+/// means the congruences contradict each other or the combined modulus overflows.
+pub fn crt(congruences: &[(i128, i128)]) -> Option<(i128, i128)> {
+    congruences.iter().try_fold(
+        (0, 1),
+        |(residue, modulus), &(next_residue, next_modulus)| {
+            if next_modulus <= 0 {
+                return None;
+            }
+
+            let divisor = gcd(modulus, next_modulus);
+            let difference = next_residue - residue;
+            if difference % divisor != 0 {
+                return None;
+            }
+
+            // Find k with residue + modulus * k ≡ next_residue, working modulo the part of
+            // next_modulus that modulus does not already cover.
+            let reduced = next_modulus / divisor;
+            let inverse = mod_inverse(modulus / divisor, reduced)?;
+            let k = ((difference / divisor).rem_euclid(reduced))
+                .checked_mul(inverse)?
+                .rem_euclid(reduced);
+            let combined = lcm(modulus, next_modulus)?;
+            let value = residue
+                .checked_add(modulus.checked_mul(k)?)?
+                .rem_euclid(combined);
+
+            Some((value, combined))
+        },
+    )
+}
+
+/// The largest `r` with `r * r <= value`.
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn isqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+
+    // Newton's method from above never undershoots, so stop once it stops decreasing.
+    let mut root = value;
+    let mut next = value / 2 + value % 2;
+    while next < root {
+        root = next;
+        next = (root + value / root) / 2;
+    }
+
+    root
+}
+
+/// `10^exponent`, or `None` if it does not fit in `T`.
+pub fn checked_pow10<T: Integer>(exponent: u32) -> Option<T> {
+    (0..exponent).try_fold(T::ONE, |power, _| power.checked_mul(T::TEN))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcd() {
+        assert_eq!(gcd(48, 18), 6);
+        assert_eq!(gcd(-48_i64, 18), 6);
+        assert_eq!(gcd(48_isize, -18), 6);
+        assert_eq!(gcd(0_u32, 7), 7);
+        assert_eq!(gcd(7_usize, 0), 7);
+        assert_eq!(gcd(0_i128, 0), 0);
+        assert_eq!(gcd(17_u64, 5), 1);
+    }
+
+    #[test]
+    fn test_lcm() {
+        assert_eq!(lcm(4, 6), Some(12));
+        assert_eq!(lcm(-4_i64, 6), Some(12));
+        assert_eq!(lcm(101_usize, 103), Some(10403));
+        assert_eq!(lcm(0_u32, 5), Some(0));
+        assert_eq!(lcm(u64::MAX, u64::MAX - 1), None);
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        let (gcd, x, y) = extended_gcd(240, 46);
+
+        assert_eq!(gcd, 2);
+        assert_eq!(240 * x + 46 * y, 2);
+    }
+
+    #[test]
+    fn test_extended_gcd_signs() {
+        for (a, b) in [(-240, 46), (240, -46), (-7, -3), (0, 5), (5, 0), (0, 0)] {
+            let (gcd, x, y) = extended_gcd(a, b);
+
+            assert!(gcd >= 0);
+            assert_eq!(a * x + b * y, gcd, "{a}, {b}");
+        }
+        assert_eq!(extended_gcd(0, -5).0, 5);
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        assert_eq!(mod_inverse(3, 11), Some(4));
+        assert_eq!(mod_inverse(-3, 11), Some(7));
+        assert_eq!(mod_inverse(101, 103), Some(51));
+        assert_eq!(mod_inverse(6, 9), None);
+        assert_eq!(mod_inverse(5, 1), Some(0));
+        assert_eq!(mod_inverse(5, 0), None);
+    }
+
+    #[test]
+    fn test_crt_coprime() {
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt(&[(3, 5), (2, 7)]), Some((23, 35)));
+        assert_eq!(crt(&[(99, 101), (102, 103)]), Some((5149, 10403)));
+    }
+
+    #[test]
+    fn test_crt_non_coprime() {
+        assert_eq!(crt(&[(2, 4), (4, 6)]), Some((10, 12)));
+        assert_eq!(crt(&[(1, 4), (0, 6)]), None);
+        assert_eq!(crt(&[(3, 6), (3, 6)]), Some((3, 6)));
+    }
+
+    #[test]
+    fn test_crt_edge_cases() {
+        assert_eq!(crt(&[]), Some((0, 1)));
+        assert_eq!(crt(&[(-1, 5)]), Some((4, 5)));
+        assert_eq!(crt(&[(1, 0)]), None);
+        assert_eq!(crt(&[(0, i128::MAX), (0, i128::MAX - 1)]), None);
+    }
+
+    #[test]
+    fn test_isqrt() {
+        assert_eq!(isqrt(0), 0);
+        assert_eq!(isqrt(1), 1);
+        assert_eq!(isqrt(15), 3);
+        assert_eq!(isqrt(16), 4);
+        assert_eq!(isqrt(17), 4);
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+        for value in 0..2000 {
+            let root = isqrt(value);
+            assert!(root * root <= value && (root + 1) * (root + 1) > value);
+        }
+    }
+
+    #[test]
+    fn test_checked_pow10() {
+        assert_eq!(checked_pow10::<usize>(0), Some(1));
+        assert_eq!(checked_pow10::<usize>(3), Some(1000));
+        assert_eq!(checked_pow10::<u64>(19), Some(10_000_000_000_000_000_000));
+        assert_eq!(checked_pow10::<u64>(20), None);
+        assert_eq!(checked_pow10::<i32>(10), None);
+    }
+}
@@ -9,6 +9,10 @@ use anyhow::Result;
 
 use crate::Day;
 
+pub mod cycle;
+pub mod digits;
+pub mod math;
+
 pub fn build_twod_vec(lines: Lines<BufReader<File>>) -> Result<Vec<Vec<char>>> {
     let mut grid: Vec<Vec<char>> = Vec::new();
     for line in lines.map_while(Result::ok) {
@@ -58,6 +62,12 @@ pub fn is_in_bounds<T>(grid: &[Vec<T>], x: isize, y: isize) -> bool {
     x < grid.len() as isize && x >= 0 && y < grid[0].len() as isize && y >= 0
 }
 
+/// Builds a grid straight from text, for tests that have no need for a data file.
+#[cfg(test)]
+pub fn grid_from_str(data: &str) -> Vec<Vec<char>> {
+    data.lines().map(|line| line.chars().collect()).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;